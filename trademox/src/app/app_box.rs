@@ -1,15 +1,164 @@
+use crate::configs::yaml_config::{
+    CacheConfig, HubConfig, LiveConfig, PacingConfig, ResampleConfig, YamlConfig,
+};
 use crate::creator::tk_creator::TickerBox;
+use crate::data::md_breaker::{BreakerProvider, CircuitBreaker};
+use crate::data::md_cache::CachedProvider;
+use crate::data::md_failover::FailoverProvider;
+use crate::data::md_files::FileProvider;
+use crate::data::md_flight::SingleFlightProvider;
+use crate::data::md_provider::{create_provider, MarketDataProvider};
+use crate::data::md_store::{LocalStore, StoredProvider};
 use crate::data::yfinance_data::YFinanceData;
+use crate::websocket::ws_hub::FeedHub;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone)]
 pub struct AppBox {
-    pub yfinance_data: YFinanceData,
+    pub provider: Arc<dyn MarketDataProvider>,
+    pub history_cache: Option<Arc<CachedProvider>>,
+    pub breaker: Option<Arc<CircuitBreaker>>,
     pub ticker_box: TickerBox,
+    pub pacing: PacingConfig,
+    pub resample: ResampleConfig,
+    pub live: LiveConfig,
+    pub hub: Arc<FeedHub>,
 }
 
 pub fn build_app() -> AppBox {
+    let yaml_config = YamlConfig::from_file("config.yaml").ok();
+    let provider_config = yaml_config
+        .as_ref()
+        .map(|c| c.provider.clone())
+        .unwrap_or_default();
+    let cache_config = yaml_config
+        .as_ref()
+        .map(|c| c.cache.clone())
+        .unwrap_or_default();
+    let store_config = yaml_config
+        .as_ref()
+        .map(|c| c.store.clone())
+        .unwrap_or_default();
+    let failover_config = yaml_config
+        .as_ref()
+        .map(|c| c.failover.clone())
+        .unwrap_or_default();
+    let breaker_config = yaml_config
+        .as_ref()
+        .map(|c| c.breaker.clone())
+        .unwrap_or_default();
+
+    let pacing_config = yaml_config
+        .as_ref()
+        .map(|c| c.pacing.clone())
+        .unwrap_or_default();
+    let resample_config = yaml_config
+        .as_ref()
+        .map(|c| c.resample.clone())
+        .unwrap_or_default();
+    let live_config = yaml_config
+        .as_ref()
+        .map(|c| c.live.clone())
+        .unwrap_or_default();
+    let hub_config = yaml_config
+        .as_ref()
+        .map(|c| c.hub.clone())
+        .unwrap_or_default();
+    // a shared history snapshot is reused no longer than the cache would keep its recent bars
+    let hub = Arc::new(FeedHub::new(
+        hub_config.buffer,
+        Duration::from_secs(cache_config.ttl_secs),
+    ));
+
+    let mut provider = create_provider(&provider_config).unwrap_or_else(|e| {
+        warn!(error = %e, "Falling back to yfinance provider");
+        Arc::new(YFinanceData::new())
+    });
+    info!(provider = provider.name(), "Market data provider selected");
+
+    // below the single-flight and caches, so only real upstream calls count
+    let breaker = breaker_config.enabled.then(|| {
+        let breaker = Arc::new(CircuitBreaker::new(&breaker_config));
+        provider = Arc::new(BreakerProvider::new(provider.clone(), breaker.clone()));
+        info!(
+            failure_threshold = breaker_config.failure_threshold,
+            open_secs = breaker_config.open_secs,
+            "Provider circuit breaker enabled"
+        );
+        breaker
+    });
+
+    if failover_config.enabled {
+        let local_store: Arc<dyn MarketDataProvider> = Arc::new(LocalStore::new(&store_config.dir));
+        let mut sources = HashMap::from([(local_store.name().to_string(), local_store)]);
+        match FileProvider::new(&provider_config.files) {
+            Ok(files) => {
+                sources.insert(files.name().to_string(), Arc::new(files));
+            }
+            Err(e) => warn!(error = %e, "Dataset files unavailable for failover"),
+        }
+        // the selected provider keeps its breaker even when it is `files` too
+        sources.insert(provider.name().to_string(), provider.clone());
+        match FailoverProvider::new(&failover_config, &sources) {
+            Ok(failover) => {
+                provider = Arc::new(failover);
+                info!(chains = ?failover_config.chains, default = ?failover_config.default, "Failover chains enabled");
+            }
+            Err(e) => warn!(error = %e, "Failover chains ignored"),
+        }
+    }
+
+    if provider_config.single_flight {
+        provider = Arc::new(SingleFlightProvider::new(provider));
+        info!("Single-flight fetches enabled");
+    }
+
+    if store_config.enabled {
+        let store = Arc::new(LocalStore::new(&store_config.dir));
+        provider = Arc::new(StoredProvider::new(provider, store, store_config.mode));
+        info!(dir = %store_config.dir, mode = ?store_config.mode, "Local store enabled");
+    }
+
+    if !cache_config.enabled {
+        return AppBox {
+            pacing: pacing_config,
+            resample: resample_config,
+            live: live_config,
+            hub,
+            breaker,
+            ..build_app_with_provider(provider)
+        };
+    }
+
+    let history_cache = Arc::new(CachedProvider::new(provider, &cache_config));
+    info!(ttl_secs = cache_config.ttl_secs, "History cache enabled");
+    AppBox {
+        provider: history_cache.clone(),
+        history_cache: Some(history_cache),
+        breaker,
+        ticker_box: TickerBox::new(),
+        pacing: pacing_config,
+        resample: resample_config,
+        live: live_config,
+        hub,
+    }
+}
+
+pub fn build_app_with_provider(provider: Arc<dyn MarketDataProvider>) -> AppBox {
     AppBox {
-        yfinance_data: YFinanceData::new(),
+        provider,
+        history_cache: None,
+        breaker: None,
         ticker_box: TickerBox::new(),
+        pacing: PacingConfig::default(),
+        resample: ResampleConfig::default(),
+        live: LiveConfig::default(),
+        hub: Arc::new(FeedHub::new(
+            HubConfig::default().buffer,
+            Duration::from_secs(CacheConfig::default().ttl_secs),
+        )),
     }
 }
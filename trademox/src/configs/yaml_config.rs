@@ -1,5 +1,9 @@
+use crate::requests::tk_asset::AssetClass;
+use crate::requests::tk_interval::TickerInterval;
+use chrono::Weekday;
 use serde::Deserialize;
 use serde_yaml;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
 
@@ -8,9 +12,475 @@ pub struct CorsConfig {
     pub origins: Vec<String>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderConfig {
+    #[serde(default = "default_provider_name")]
+    pub name: String,
+    // concurrent identical fetches share one upstream call
+    #[serde(default = "default_single_flight")]
+    pub single_flight: bool,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    // chunks of one long range fetched at the same time
+    #[serde(default = "default_chunk_concurrency")]
+    pub chunk_concurrency: usize,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub files: FilesConfig,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            name: default_provider_name(),
+            single_flight: default_single_flight(),
+            timeout_secs: default_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            chunk_concurrency: default_chunk_concurrency(),
+            retry: RetryConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            files: FilesConfig::default(),
+        }
+    }
+}
+
+fn default_provider_name() -> String {
+    "yfinance".to_string()
+}
+
+fn default_single_flight() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_chunk_concurrency() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetryConfig {
+    // retries after the first attempt; 0 turns retrying off
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    #[serde(default = "default_retry_max_delay_ms")]
+    pub max_delay_ms: u64,
+    #[serde(default = "default_jitter")]
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            base_delay_ms: default_base_delay_ms(),
+            max_delay_ms: default_retry_max_delay_ms(),
+            jitter: default_jitter(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_base_delay_ms() -> u64 {
+    250
+}
+
+fn default_retry_max_delay_ms() -> u64 {
+    5000
+}
+
+fn default_jitter() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    // shared by every upstream call, retries included; 0 turns the limit off
+    #[serde(default = "default_requests_per_sec")]
+    pub requests_per_sec: f64,
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_sec: default_requests_per_sec(),
+            burst: default_burst(),
+        }
+    }
+}
+
+fn default_requests_per_sec() -> f64 {
+    5.0
+}
+
+fn default_burst() -> u32 {
+    10
+}
+
+// Per-symbol `<TICKER>.csv` or `<TICKER>.parquet` files under `dir`, all with bars of `interval`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FilesConfig {
+    #[serde(default = "default_files_dir")]
+    pub dir: String,
+    #[serde(default)]
+    pub interval: TickerInterval,
+    // chrono format such as `%Y-%m-%d %H:%M`, or `unix`, `unix_ms` and `rfc3339`
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    // IANA zone of timestamps written without an offset
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    #[serde(default)]
+    pub columns: ColumnMapping,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_files_dir(),
+            interval: TickerInterval::default(),
+            date_format: default_date_format(),
+            timezone: default_timezone(),
+            delimiter: default_delimiter(),
+            columns: ColumnMapping::default(),
+        }
+    }
+}
+
+fn default_files_dir() -> String {
+    "data/files".to_string()
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+// Header names of the candle fields; optional columns set to null are left empty
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnMapping {
+    #[serde(default = "default_time_column")]
+    pub time: String,
+    #[serde(default = "default_open_column")]
+    pub open: String,
+    #[serde(default = "default_high_column")]
+    pub high: String,
+    #[serde(default = "default_low_column")]
+    pub low: String,
+    #[serde(default = "default_close_column")]
+    pub close: String,
+    #[serde(default = "default_volume_column")]
+    pub volume: Option<String>,
+    #[serde(default)]
+    pub unadjusted_close: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            time: default_time_column(),
+            open: default_open_column(),
+            high: default_high_column(),
+            low: default_low_column(),
+            close: default_close_column(),
+            volume: default_volume_column(),
+            unadjusted_close: None,
+        }
+    }
+}
+
+fn default_time_column() -> String {
+    "time".to_string()
+}
+
+fn default_open_column() -> String {
+    "open".to_string()
+}
+
+fn default_high_column() -> String {
+    "high".to_string()
+}
+
+fn default_low_column() -> String {
+    "low".to_string()
+}
+
+fn default_close_column() -> String {
+    "close".to_string()
+}
+
+fn default_volume_column() -> Option<String> {
+    Some("volume".to_string())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FailoverConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // sources tried in order for tickers of an asset class without a chain of their own
+    #[serde(default = "default_failover_chain")]
+    pub default: Vec<String>,
+    #[serde(default)]
+    pub chains: HashMap<AssetClass, Vec<String>>,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default: default_failover_chain(),
+            chains: HashMap::new(),
+        }
+    }
+}
+
+fn default_failover_chain() -> Vec<String> {
+    vec![default_provider_name()]
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BreakerConfig {
+    #[serde(default = "default_breaker_enabled")]
+    pub enabled: bool,
+    // consecutive upstream outages that open the circuit
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    // how long an open circuit fails fast before one trial call is let through
+    #[serde(default = "default_open_secs")]
+    pub open_secs: u64,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_breaker_enabled(),
+            failure_threshold: default_failure_threshold(),
+            open_secs: default_open_secs(),
+        }
+    }
+}
+
+fn default_breaker_enabled() -> bool {
+    true
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_open_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheConfig {
+    #[serde(default = "default_cache_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    // ticker and interval pairs kept; the least recently used one goes first
+    #[serde(default = "default_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cache_enabled(),
+            ttl_secs: default_cache_ttl_secs(),
+            max_entries: default_cache_max_entries(),
+        }
+    }
+}
+
+fn default_cache_enabled() -> bool {
+    true
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_cache_max_entries() -> usize {
+    512
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreMode {
+    OfflineOnly,
+    #[default]
+    CacheFirst,
+    NetworkFirst,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StoreConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_store_dir")]
+    pub dir: String,
+    #[serde(default)]
+    pub mode: StoreMode,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_store_dir(),
+            mode: StoreMode::default(),
+        }
+    }
+}
+
+fn default_store_dir() -> String {
+    "data/store".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PacingConfig {
+    #[serde(default = "default_pace")]
+    pub default_pace: String,
+    #[serde(default = "default_max_rate")]
+    pub max_rate: f64,
+    #[serde(default = "default_allow_burst")]
+    pub allow_burst: bool,
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl Default for PacingConfig {
+    fn default() -> Self {
+        Self {
+            default_pace: default_pace(),
+            max_rate: default_max_rate(),
+            allow_burst: default_allow_burst(),
+            max_delay_ms: default_max_delay_ms(),
+        }
+    }
+}
+
+fn default_pace() -> String {
+    "rate:10".to_string()
+}
+
+fn default_max_rate() -> f64 {
+    1000.0
+}
+
+fn default_allow_burst() -> bool {
+    true
+}
+
+fn default_max_delay_ms() -> u64 {
+    5000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResampleConfig {
+    // first day of a `W` bar, e.g. `fri` for fiscal weeks
+    #[serde(default = "default_week_anchor")]
+    pub week_anchor: Weekday,
+}
+
+impl Default for ResampleConfig {
+    fn default() -> Self {
+        Self {
+            week_anchor: default_week_anchor(),
+        }
+    }
+}
+
+fn default_week_anchor() -> Weekday {
+    Weekday::Mon
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LiveConfig {
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: default_poll_interval_ms(),
+        }
+    }
+}
+
+fn default_poll_interval_ms() -> u64 {
+    5000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HubConfig {
+    // live bars a slow consumer may fall behind before it is resynced
+    #[serde(default = "default_hub_buffer")]
+    pub buffer: usize,
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        Self {
+            buffer: default_hub_buffer(),
+        }
+    }
+}
+
+fn default_hub_buffer() -> usize {
+    256
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct YamlConfig {
     pub cors: CorsConfig,
+    #[serde(default)]
+    pub provider: ProviderConfig,
+    #[serde(default)]
+    pub failover: FailoverConfig,
+    #[serde(default)]
+    pub breaker: BreakerConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub store: StoreConfig,
+    #[serde(default)]
+    pub pacing: PacingConfig,
+    #[serde(default)]
+    pub resample: ResampleConfig,
+    #[serde(default)]
+    pub live: LiveConfig,
+    #[serde(default)]
+    pub hub: HubConfig,
 }
 impl YamlConfig {
     pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
@@ -1 +1,2 @@
+pub mod tk_arrow;
 pub mod tk_creator;
@@ -0,0 +1,84 @@
+use crate::creator::tk_creator::TickerParams;
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, TimestampMillisecondArray, UInt64Array};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+pub const ARROW_STREAM_MIME: &str = "application/vnd.apache.arrow.stream";
+
+// Fixed column layout of every Arrow snapshot; prices are adjusted unless noted
+pub fn candle_schema() -> Schema {
+    let price = |name| Field::new(name, DataType::Float64, false);
+    Schema::new(vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        price("open"),
+        price("high"),
+        price("low"),
+        price("close"),
+        Field::new("volume", DataType::UInt64, true),
+        Field::new("unadjusted_close", DataType::Float64, true),
+    ])
+}
+
+pub fn candles_to_record_batch(candles: &[TickerParams]) -> Result<RecordBatch> {
+    let price = |f: fn(&TickerParams) -> Decimal| -> Result<ArrayRef> {
+        let values = candles
+            .iter()
+            .map(|c| price_to_f64(f(c), c))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(Float64Array::from(values)))
+    };
+    let unadjusted_close = candles
+        .iter()
+        .map(|c| c.unadjusted_close.map(|p| price_to_f64(p, c)).transpose())
+        .collect::<Result<Vec<_>>>()?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampMillisecondArray::from_iter_values(
+                candles.iter().map(|c| c.time.timestamp_millis()),
+            )
+            .with_timezone("UTC"),
+        ),
+        price(|c| c.open)?,
+        price(|c| c.high)?,
+        price(|c| c.low)?,
+        price(|c| c.close)?,
+        Arc::new(UInt64Array::from_iter(candles.iter().map(|c| c.volume))),
+        Arc::new(Float64Array::from(unadjusted_close)),
+    ];
+
+    RecordBatch::try_new(Arc::new(candle_schema()), columns)
+        .context("Building Arrow record batch failed")
+}
+
+fn price_to_f64(price: Decimal, candle: &TickerParams) -> Result<f64> {
+    price
+        .to_f64()
+        .with_context(|| format!("Price {price} at {} does not fit an f64", candle.time))
+}
+
+// One record batch in the Arrow IPC stream format
+pub fn candles_to_arrow_ipc(candles: &[TickerParams]) -> Result<Vec<u8>> {
+    let batch = candles_to_record_batch(candles)?;
+
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema())
+        .context("Creating Arrow IPC writer failed")?;
+    writer
+        .write(&batch)
+        .context("Writing Arrow record batch failed")?;
+    writer
+        .finish()
+        .context("Finishing Arrow IPC stream failed")?;
+    drop(writer);
+
+    Ok(bytes)
+}
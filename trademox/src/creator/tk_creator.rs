@@ -1,21 +1,95 @@
+use crate::data::md_provider::{MarketAction, MarketCandle};
+use crate::data::md_ranges::DayRange;
 use crate::errors::tk_errors::TickerDateRangeError::InvalidDateRange;
 use anyhow::Result;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::ser::Error as _;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
 use tracing::info;
-use yfinance_rs::Candle;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TickerParams {
-    pub time: NaiveDate,
+    pub time: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_price")]
     pub open: Decimal,
+    #[serde(serialize_with = "serialize_price")]
     pub high: Decimal,
+    #[serde(serialize_with = "serialize_price")]
     pub low: Decimal,
+    #[serde(serialize_with = "serialize_price")]
     pub close: Decimal,
+    pub volume: Option<u64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_price"
+    )]
+    pub unadjusted_close: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub indicators: BTreeMap<String, f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TickerEvent {
+    Dividend {
+        time: DateTime<Utc>,
+        #[serde(serialize_with = "serialize_price")]
+        amount: Decimal,
+    },
+    Split {
+        time: DateTime<Utc>,
+        numerator: u32,
+        denominator: u32,
+    },
+}
+
+impl TickerEvent {
+    pub fn time(&self) -> DateTime<Utc> {
+        match self {
+            TickerEvent::Dividend { time, .. } | TickerEvent::Split { time, .. } => *time,
+        }
+    }
+}
+
+// Prices stay exact strings in JSON; binary formats get compact floats
+fn serialize_price<S: Serializer>(price: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        Serialize::serialize(price, serializer)
+    } else {
+        let price = price
+            .to_f64()
+            .ok_or_else(|| S::Error::custom(format!("price {price} does not fit an f64")))?;
+        serializer.serialize_f64(price)
+    }
+}
+
+fn serialize_optional_price<S: Serializer>(
+    price: &Option<Decimal>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match price {
+        Some(price) => serialize_price(price, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TickerSnapshot {
+    pub candles: Vec<TickerParams>,
+    pub events: Vec<TickerEvent>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<DayRange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TickerBox;
 
 impl TickerBox {
@@ -24,7 +98,7 @@ impl TickerBox {
     }
     pub async fn create_ticker_data(
         &self,
-        history_data: Vec<Candle>,
+        history_data: Vec<MarketCandle>,
         start: NaiveDate,
         end: NaiveDate,
     ) -> Result<Vec<TickerParams>> {
@@ -35,15 +109,42 @@ impl TickerBox {
         let ticker_data = history_data
             .into_iter()
             .map(|h| TickerParams {
-                time: h.ts.date_naive(),
-                open: h.open.amount(),
-                high: h.high.amount(),
-                low: h.low.amount(),
-                close: h.close.amount(),
+                time: h.ts,
+                open: h.open,
+                high: h.high,
+                low: h.low,
+                close: h.close,
+                volume: h.volume,
+                unadjusted_close: h.unadjusted_close,
+                indicators: BTreeMap::new(),
             })
             .collect();
 
         info!(start = %start, end = %end, "Created creator data");
         Ok(ticker_data)
     }
+
+    pub fn create_ticker_events(
+        &self,
+        actions: Vec<MarketAction>,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Vec<TickerEvent> {
+        actions
+            .into_iter()
+            .filter(|a| (start..=end).contains(&a.ts().date_naive()))
+            .map(|a| match a {
+                MarketAction::Dividend { ts, amount } => TickerEvent::Dividend { time: ts, amount },
+                MarketAction::Split {
+                    ts,
+                    numerator,
+                    denominator,
+                } => TickerEvent::Split {
+                    time: ts,
+                    numerator,
+                    denominator,
+                },
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,180 @@
+use crate::configs::yaml_config::BreakerConfig;
+use crate::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use crate::errors::md_errors::ProviderError::{self, CircuitOpen};
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderHealth {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures: u32,
+    opened_at: Option<Instant>,
+    // an open circuit lets one trial call through per `open_for`, so a dropped trial is not waited on forever
+    trial_at: Option<Instant>,
+    last_error: Option<String>,
+}
+
+// Opens after `failure_threshold` outages in a row and fails fast until a trial call succeeds
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    open_for: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &BreakerConfig) -> Self {
+        Self {
+            threshold: config.failure_threshold.max(1),
+            open_for: Duration::from_secs(config.open_secs),
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    // Time left before the next trial when the call has to fail fast
+    fn acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+        let waiting_since = state.trial_at.unwrap_or(opened_at);
+        let elapsed = waiting_since.elapsed();
+        if elapsed < self.open_for {
+            return Err(self.open_for - elapsed);
+        }
+        state.trial_at = Some(Instant::now());
+        Ok(())
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.opened_at.is_some() {
+            info!("Provider circuit closed");
+        }
+        *state = BreakerState::default();
+    }
+
+    fn record_outage(&self, e: &anyhow::Error) {
+        let mut state = self.state.lock().unwrap();
+        state.failures += 1;
+        state.last_error = Some(format!("{e:#}"));
+        if state.trial_at.is_some()
+            || (state.opened_at.is_none() && state.failures >= self.threshold)
+        {
+            warn!(failures = state.failures, open_for = ?self.open_for, "Provider circuit opened");
+            state.opened_at = Some(Instant::now());
+            state.trial_at = None;
+        }
+    }
+
+    pub fn health(&self) -> ProviderHealth {
+        let state = self.state.lock().unwrap();
+        let (circuit, retry_in) = match (state.opened_at, state.trial_at) {
+            (None, _) => (CircuitState::Closed, None),
+            (Some(_), Some(_)) => (CircuitState::HalfOpen, None),
+            (Some(opened_at), None) => (
+                CircuitState::Open,
+                Some(self.open_for.saturating_sub(opened_at.elapsed())),
+            ),
+        };
+        ProviderHealth {
+            state: circuit,
+            consecutive_failures: state.failures,
+            last_error: state.last_error.clone(),
+            retry_in_secs: retry_in.map(|d| d.as_secs()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BreakerProvider {
+    inner: Arc<dyn MarketDataProvider>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl BreakerProvider {
+    pub fn new(inner: Arc<dyn MarketDataProvider>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+
+    async fn guard<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        if let Err(retry_in) = self.breaker.acquire() {
+            return Err(CircuitOpen {
+                provider: self.inner.name().to_string(),
+                retry_in_secs: retry_in.as_secs(),
+            }
+            .into());
+        }
+
+        let result = call.await;
+        match &result {
+            Err(e) if self.inner.is_outage(e) => self.breaker.record_outage(e),
+            // the upstream answered, even if it turned the request down
+            _ => self.breaker.record_success(),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for BreakerProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.guard(self.inner.fetch_history_data(ticker, interval, start, end))
+            .await
+    }
+
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        matches!(e.downcast_ref::<ProviderError>(), Some(CircuitOpen { .. }))
+            || self.inner.is_outage(e)
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        self.guard(self.inner.fetch_latest_bars(ticker, interval, since))
+            .await
+    }
+}
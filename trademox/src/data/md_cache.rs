@@ -0,0 +1,339 @@
+use crate::configs::yaml_config::CacheConfig;
+use crate::data::md_provider::{
+    join_sources, MarketAction, MarketCandle, MarketDataProvider, MarketHistory,
+    ProviderCapabilities,
+};
+use crate::data::md_ranges::{split_recent, uncovered_ranges, DayRange};
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, warn};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    ticker: String,
+    interval: TickerInterval,
+}
+
+#[derive(Debug, Clone)]
+struct CoveredRange {
+    start: NaiveDate,
+    end: NaiveDate,
+    expires_at: Option<Instant>,
+    // bars served from a fallback while the upstream was down
+    stale: bool,
+    // days in the range the upstream returned no bars for
+    gaps: Vec<DayRange>,
+}
+
+#[derive(Debug, Default)]
+struct CacheEntry {
+    candles: BTreeMap<DateTime<Utc>, MarketCandle>,
+    actions: BTreeMap<DateTime<Utc>, MarketAction>,
+    covered: Vec<CoveredRange>,
+    sources: BTreeSet<String>,
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+// Keeps fetched history per ticker and interval; bars from yesterday onwards expire after `ttl`
+#[derive(Debug)]
+pub struct CachedProvider {
+    inner: Arc<dyn MarketDataProvider>,
+    ttl: std::time::Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    uses: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedProvider {
+    pub fn new(inner: Arc<dyn MarketDataProvider>, config: &CacheConfig) -> Self {
+        Self {
+            inner,
+            ttl: std::time::Duration::from_secs(config.ttl_secs),
+            max_entries: config.max_entries.max(1),
+            entries: Mutex::new(HashMap::new()),
+            uses: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    // Entry for `key`, marked as just used; a new one evicts the least recently used when full
+    fn touch<'a>(
+        &self,
+        entries: &'a mut HashMap<CacheKey, CacheEntry>,
+        key: &CacheKey,
+    ) -> &'a mut CacheEntry {
+        if !entries.contains_key(key) && entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                debug!(ticker = %oldest.ticker, interval = %oldest.interval, "History cache entry evicted");
+                entries.remove(&oldest);
+            }
+        }
+        let entry = entries.entry(key.clone()).or_default();
+        entry.last_used = self.uses.fetch_add(1, Ordering::Relaxed) + 1;
+        entry
+    }
+
+    fn missing_ranges(&self, key: &CacheKey, start: NaiveDate, end: NaiveDate) -> Vec<DayRange> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = self.touch(&mut entries, key);
+
+        // expired ranges are refetched, but their bars still count for a stale answer
+        let now = Instant::now();
+        let covered = entry
+            .covered
+            .iter()
+            .filter(|r| r.expires_at.is_none_or(|expires_at| expires_at > now))
+            .map(|r| (r.start, r.end))
+            .collect::<Vec<_>>();
+        uncovered_ranges(&covered, start, end)
+    }
+
+    // Parts of `ranges` the cache never held, expired or not
+    fn holes(&self, key: &CacheKey, ranges: &[DayRange]) -> Vec<DayRange> {
+        let entries = self.entries.lock().unwrap();
+        let held = entries
+            .get(key)
+            .map(|entry| {
+                entry
+                    .covered
+                    .iter()
+                    .map(|r| (r.start, r.end))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        ranges
+            .iter()
+            .flat_map(|(start, end)| uncovered_ranges(&held, *start, *end))
+            .collect()
+    }
+
+    fn store(&self, key: &CacheKey, start: NaiveDate, end: NaiveDate, history: MarketHistory) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = self.touch(&mut entries, key);
+
+        for candle in history.candles {
+            entry.candles.insert(candle.ts, candle);
+        }
+        for action in history.actions {
+            entry.actions.insert(action.ts(), action);
+        }
+        entry.sources.extend(history.source);
+
+        // expired ranges the fetch replaced
+        let now = Instant::now();
+        entry.covered.retain(|r| {
+            r.expires_at.is_none_or(|expires_at| expires_at > now) || r.start < start || r.end > end
+        });
+        // stale bars and reported gaps are held for reads, but already expired so they get fetched again
+        let gaps = clip_ranges(&history.gaps, start, end);
+        if history.stale {
+            entry.covered.push(CoveredRange {
+                start,
+                end,
+                expires_at: Some(now),
+                stale: true,
+                gaps,
+            });
+            return;
+        }
+        for &(gap_start, gap_end) in &gaps {
+            entry.covered.push(CoveredRange {
+                start: gap_start,
+                end: gap_end,
+                expires_at: Some(now),
+                stale: false,
+                gaps: vec![(gap_start, gap_end)],
+            });
+        }
+        for (start, end) in uncovered_ranges(&gaps, start, end) {
+            let (settled, recent) = split_recent(start, end);
+            if let Some((start, end)) = settled {
+                entry.covered.push(CoveredRange {
+                    start,
+                    end,
+                    expires_at: None,
+                    stale: false,
+                    gaps: Vec::new(),
+                });
+            }
+            if let Some((start, end)) = recent {
+                entry.covered.push(CoveredRange {
+                    start,
+                    end,
+                    expires_at: Some(now + self.ttl),
+                    stale: false,
+                    gaps: Vec::new(),
+                });
+            }
+        }
+    }
+
+    fn read(&self, key: &CacheKey, start: NaiveDate, end: NaiveDate) -> MarketHistory {
+        // days as the provider matched them when the bars were fetched
+        let capabilities = self.inner.capabilities();
+        let entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get(key) else {
+            return MarketHistory::default();
+        };
+
+        let in_range = |ts: &DateTime<Utc>| (start..=end).contains(&capabilities.day_of(ts));
+        let candles = entry
+            .candles
+            .iter()
+            .filter(|(ts, _)| in_range(ts))
+            .map(|(_, c)| c.clone())
+            .collect::<Vec<_>>();
+        MarketHistory {
+            source: if candles.is_empty() {
+                None
+            } else {
+                join_sources(entry.sources.iter().cloned().map(Some))
+            },
+            candles,
+            actions: entry
+                .actions
+                .iter()
+                .filter(|(ts, _)| in_range(ts))
+                .map(|(_, a)| a.clone())
+                .collect(),
+            stale: entry
+                .covered
+                .iter()
+                .any(|r| r.stale && r.end >= start && r.start <= end),
+            gaps: clip_ranges(
+                &entry
+                    .covered
+                    .iter()
+                    .flat_map(|r| r.gaps.iter().copied())
+                    .collect::<Vec<_>>(),
+                start,
+                end,
+            ),
+        }
+    }
+}
+
+// Parts of `ranges` inside [start, end], in date order
+fn clip_ranges(ranges: &[DayRange], start: NaiveDate, end: NaiveDate) -> Vec<DayRange> {
+    let mut clipped = ranges
+        .iter()
+        .filter(|(range_start, range_end)| *range_end >= start && *range_start <= end)
+        .map(|(range_start, range_end)| ((*range_start).max(start), (*range_end).min(end)))
+        .collect::<Vec<_>>();
+    clipped.sort();
+    clipped.dedup();
+    clipped
+}
+
+#[async_trait]
+impl MarketDataProvider for CachedProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        let key = CacheKey {
+            ticker: ticker.to_string(),
+            interval,
+        };
+
+        let missing = self.missing_ranges(&key, start, end);
+        if missing.is_empty() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        for (position, &(miss_start, miss_end)) in missing.iter().enumerate() {
+            let history = match self
+                .inner
+                .fetch_history_data(ticker, interval, miss_start, miss_end)
+                .await
+            {
+                Ok(history) => history,
+                // expired bars still beat no bars while the upstream is down, but not a rejected request
+                Err(e) if self.inner.is_outage(&e) => {
+                    let cached = self.read(&key, start, end);
+                    if cached.candles.is_empty() {
+                        return Err(e);
+                    }
+                    warn!(error = %e, ticker, %interval, "Upstream fetch failed, serving stale cache");
+                    let mut gaps = cached.gaps.clone();
+                    gaps.extend(self.holes(&key, &missing[position..]));
+                    gaps.sort();
+                    return Ok(MarketHistory {
+                        stale: true,
+                        gaps,
+                        ..cached
+                    });
+                }
+                Err(e) => return Err(e),
+            };
+            self.store(&key, miss_start, miss_end, history);
+        }
+
+        let stats = self.stats();
+        debug!(ticker, %interval, hits = stats.hits, misses = stats.misses, "History cache lookup");
+
+        Ok(self.read(&key, start, end))
+    }
+
+    // The outermost layer, so callers ask it about errors from the whole stack below
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        self.inner.is_outage(e)
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        self.inner.fetch_latest_bars(ticker, interval, since).await
+    }
+}
@@ -0,0 +1,89 @@
+use crate::data::md_provider::{join_sources, MarketCandle, MarketHistory};
+use crate::data::md_ranges::{chunk_ranges, DayRange};
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::collections::BTreeMap;
+use std::future::Future;
+
+// Fetches [start, end] chunk by chunk, up to `concurrency` at a time, and merges what falls inside it
+pub async fn fetch_chunked<F, Fut>(
+    start: NaiveDate,
+    end: NaiveDate,
+    chunk_days: i64,
+    concurrency: usize,
+    fetch: F,
+) -> Result<MarketHistory>
+where
+    F: FnMut(DayRange) -> Fut,
+    Fut: Future<Output = Result<MarketHistory>>,
+{
+    let chunks = stream::iter(chunk_ranges(start, end, chunk_days))
+        .map(fetch)
+        .buffered(concurrency.max(1))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut history = merge_chunks(chunks);
+    let in_range = |ts: &DateTime<Utc>| (start..=end).contains(&ts.date_naive());
+    history.candles.retain(|c| in_range(&c.ts));
+    history.actions.retain(|a| in_range(&a.ts()));
+    Ok(history)
+}
+
+// Candles with strictly increasing, unique timestamps; a bar repeated at a chunk boundary keeps the later copy
+pub fn merge_chunks(chunks: Vec<MarketHistory>) -> MarketHistory {
+    let mut candles = BTreeMap::new();
+    let mut actions = Vec::new();
+    let mut stale = false;
+    let mut sources = Vec::new();
+    let mut gaps = Vec::new();
+    for chunk in chunks {
+        stale |= chunk.stale;
+        gaps.extend(chunk.gaps);
+        sources.push(chunk.source);
+        for candle in chunk.candles {
+            candles.insert(candle.ts, candle);
+        }
+        for action in chunk.actions {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+    }
+
+    actions.sort_by_key(|action| action.ts());
+    MarketHistory {
+        candles: candles.into_values().collect(),
+        actions,
+        stale,
+        source: join_sources(sources),
+        gaps,
+    }
+}
+
+// Day ranges in [start, end] longer than `max_missing_days` without a single bar
+pub fn day_gaps(
+    candles: &[MarketCandle],
+    start: NaiveDate,
+    end: NaiveDate,
+    max_missing_days: i64,
+) -> Vec<DayRange> {
+    let days = candles
+        .iter()
+        .map(|c| c.ts.date_naive())
+        .filter(|day| (start..=end).contains(day));
+
+    let mut gaps = Vec::new();
+    let mut cursor = start;
+    for day in days.chain([end + Duration::days(1)]) {
+        if day < cursor {
+            continue;
+        }
+        if (day - cursor).num_days() > max_missing_days {
+            gaps.push((cursor, day - Duration::days(1)));
+        }
+        cursor = day + Duration::days(1);
+    }
+    gaps
+}
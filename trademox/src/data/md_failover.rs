@@ -0,0 +1,196 @@
+use crate::configs::yaml_config::FailoverConfig;
+use crate::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use crate::errors::md_errors::ProviderError::{self, AllSourcesFailed, UnknownProvider};
+use crate::requests::tk_asset::AssetClass;
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+type Chain = Vec<Arc<dyn MarketDataProvider>>;
+
+// Tries the sources configured for the ticker's asset class in order; the first to return bars wins
+#[derive(Debug)]
+pub struct FailoverProvider {
+    default: Chain,
+    chains: HashMap<AssetClass, Chain>,
+}
+
+impl FailoverProvider {
+    // `sources` holds every provider a chain may name, keyed by provider name
+    pub fn new(
+        config: &FailoverConfig,
+        sources: &HashMap<String, Arc<dyn MarketDataProvider>>,
+    ) -> Result<Self> {
+        let resolve = |names: &[String]| -> Result<Chain> {
+            names
+                .iter()
+                .map(|name| {
+                    sources.get(name).cloned().ok_or_else(|| {
+                        UnknownProvider {
+                            name: name.to_string(),
+                        }
+                        .into()
+                    })
+                })
+                .collect()
+        };
+
+        let default = resolve(&config.default)?;
+        if default.is_empty() {
+            return Err(anyhow!(
+                "The default failover chain needs at least one source"
+            ));
+        }
+        let chains = config
+            .chains
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(class, names)| Ok((*class, resolve(names)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(Self { default, chains })
+    }
+
+    fn chain(&self, ticker: &str) -> &Chain {
+        self.chains
+            .get(&AssetClass::from_ticker(ticker))
+            .unwrap_or(&self.default)
+    }
+
+    fn sources(&self, ticker: &str) -> String {
+        self.chain(ticker)
+            .iter()
+            .map(|source| source.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for FailoverProvider {
+    fn name(&self) -> &str {
+        "failover"
+    }
+
+    // What the default chain can serve, so requests validate as they would without failover;
+    // days follow its first source
+    fn capabilities(&self) -> ProviderCapabilities {
+        let mut intervals = Vec::new();
+        let mut supports_actions = false;
+        let mut timezone = None;
+        for source in &self.default {
+            let capabilities = source.capabilities();
+            timezone.get_or_insert(capabilities.timezone);
+            supports_actions |= capabilities.supports_actions;
+            for interval in capabilities.intervals {
+                if !intervals.contains(&interval) {
+                    intervals.push(interval);
+                }
+            }
+        }
+        intervals.sort();
+        ProviderCapabilities {
+            intervals,
+            supports_actions,
+            timezone: timezone.unwrap_or(Tz::UTC),
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        let mut empty = None;
+        let mut last_error = None;
+        let mut outage = false;
+        for (position, source) in self.chain(ticker).iter().enumerate() {
+            match source
+                .fetch_history_data(ticker, interval, start, end)
+                .await
+            {
+                Ok(mut history) => {
+                    history
+                        .source
+                        .get_or_insert_with(|| source.name().to_string());
+                    // an empty answer may just be a source that does not know the ticker
+                    if history.candles.is_empty() {
+                        empty.get_or_insert(history);
+                        continue;
+                    }
+                    if position > 0 {
+                        info!(
+                            ticker,
+                            source = source.name(),
+                            "Served by a fallback source"
+                        );
+                    }
+                    return Ok(history);
+                }
+                Err(e) => {
+                    warn!(error = %e, ticker, source = source.name(), "Source failed, trying the next one");
+                    outage |= source.is_outage(&e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(history) = empty {
+            return Ok(history);
+        }
+        let context = AllSourcesFailed {
+            ticker: ticker.to_string(),
+            sources: self.sources(ticker),
+            outage,
+        };
+        Err(match last_error {
+            Some(e) => e.context(context),
+            None => context.into(),
+        })
+    }
+
+    // One source being down is an outage, even when a later one then rejected the request
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        match e.downcast_ref::<ProviderError>() {
+            Some(AllSourcesFailed { outage, .. }) => *outage,
+            _ => true,
+        }
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        let mut last_error = None;
+        let mut outage = false;
+        for source in self.chain(ticker) {
+            match source.fetch_latest_bars(ticker, interval, since).await {
+                Ok(bars) if !bars.is_empty() => return Ok(bars),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(error = %e, ticker, source = source.name(), "Source failed, trying the next one");
+                    outage |= source.is_outage(&e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) => Err(e.context(AllSourcesFailed {
+                ticker: ticker.to_string(),
+                sources: self.sources(ticker),
+                outage,
+            })),
+            None => Ok(Vec::new()),
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use crate::configs::yaml_config::{ColumnMapping, FilesConfig};
+use crate::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use crate::data::md_store::symbol_path_name;
+use crate::errors::md_errors::DatasetError::{
+    self, IntervalMismatch, MalformedRows, MissingColumn, NotFound, UnknownTimezone,
+};
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::{anyhow, Context, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::temporal_conversions::date32_to_datetime;
+use arrow_array::types::{Date32Type, TimestampMillisecondType};
+use arrow_array::{Array, ArrayRef, StringArray};
+use arrow_cast::cast;
+use arrow_schema::{DataType, TimeUnit};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::fs;
+use tokio::task::spawn_blocking;
+use tracing::info;
+
+// Malformed rows listed in the error; the rest are only counted
+const MAX_REPORTED_ROWS: usize = 10;
+
+#[derive(Debug, Clone)]
+enum DateFormat {
+    Unix,
+    UnixMs,
+    Rfc3339,
+    Pattern(String),
+}
+
+impl DateFormat {
+    fn new(format: &str) -> Self {
+        match format {
+            "unix" => DateFormat::Unix,
+            "unix_ms" => DateFormat::UnixMs,
+            "rfc3339" => DateFormat::Rfc3339,
+            pattern => DateFormat::Pattern(pattern.to_string()),
+        }
+    }
+}
+
+// Cells of one row, `None` when empty or null
+#[derive(Debug, Default)]
+struct RawRow<'a> {
+    open: Option<&'a str>,
+    high: Option<&'a str>,
+    low: Option<&'a str>,
+    close: Option<&'a str>,
+    volume: Option<&'a str>,
+    unadjusted_close: Option<&'a str>,
+}
+
+#[derive(Debug)]
+struct RowParser {
+    date_format: DateFormat,
+    timezone: Tz,
+    delimiter: u8,
+    columns: ColumnMapping,
+}
+
+impl RowParser {
+    fn read(&self, path: &Path) -> Result<Vec<MarketCandle>> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("parquet") => self.read_parquet(path),
+            _ => self.read_csv(path),
+        }
+    }
+
+    fn read_csv(&self, path: &Path) -> Result<Vec<MarketCandle>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_path(path)
+            .with_context(|| format!("Reading {} failed", path.display()))?;
+        let headers = reader.headers()?.clone();
+        let index = |column: &str| {
+            headers
+                .iter()
+                .position(|h| h == column)
+                .ok_or_else(|| missing_column(column, path))
+        };
+        let columns = &self.columns;
+        let time = index(&columns.time)?;
+        let open = index(&columns.open)?;
+        let high = index(&columns.high)?;
+        let low = index(&columns.low)?;
+        let close = index(&columns.close)?;
+        let volume = columns.volume.as_deref().map(index).transpose()?;
+        let unadjusted_close = columns.unadjusted_close.as_deref().map(index).transpose()?;
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) if matches!(e.kind(), csv::ErrorKind::Io(_)) => {
+                    return Err(e).with_context(|| format!("Reading {} failed", path.display()))
+                }
+                Err(e) => {
+                    rows.push((e.position().map_or(0, |p| p.line()), Err(e.to_string())));
+                    continue;
+                }
+            };
+            let field = |i: usize| record.get(i).filter(|v| !v.is_empty());
+            let ts = field(time)
+                .ok_or_else(|| format!("missing `{}`", columns.time))
+                .and_then(|t| self.parse_time(t));
+            let row = RawRow {
+                open: field(open),
+                high: field(high),
+                low: field(low),
+                close: field(close),
+                volume: volume.and_then(field),
+                unadjusted_close: unadjusted_close.and_then(field),
+            };
+            let line = record.position().map_or(0, |p| p.line());
+            rows.push((line, ts.and_then(|ts| self.candle(ts, row))));
+        }
+        collect_rows(path, "line", rows)
+    }
+
+    fn read_parquet(&self, path: &Path) -> Result<Vec<MarketCandle>> {
+        let file =
+            File::open(path).with_context(|| format!("Reading {} failed", path.display()))?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .and_then(|builder| builder.build())
+            .with_context(|| format!("Reading {} failed", path.display()))?;
+
+        let columns = &self.columns;
+        let mut rows = Vec::new();
+        for batch in reader {
+            let batch = batch.with_context(|| format!("Reading {} failed", path.display()))?;
+            let column = |name: &str| {
+                batch
+                    .column_by_name(name)
+                    .ok_or_else(|| missing_column(name, path))
+            };
+            let text = |name: &str| -> Result<StringArray> {
+                Ok(cast(column(name)?, &DataType::Utf8)?
+                    .as_string::<i32>()
+                    .clone())
+            };
+            let times = self.parquet_times(column(&columns.time)?)?;
+            let open = text(&columns.open)?;
+            let high = text(&columns.high)?;
+            let low = text(&columns.low)?;
+            let close = text(&columns.close)?;
+            let volume = columns.volume.as_deref().map(text).transpose()?;
+            let unadjusted_close = columns.unadjusted_close.as_deref().map(text).transpose()?;
+
+            for (i, ts) in times.into_iter().enumerate() {
+                let row = RawRow {
+                    open: value(&open, i),
+                    high: value(&high, i),
+                    low: value(&low, i),
+                    close: value(&close, i),
+                    volume: volume.as_ref().and_then(|a| value(a, i)),
+                    unadjusted_close: unadjusted_close.as_ref().and_then(|a| value(a, i)),
+                };
+                let number = rows.len() as u64 + 1;
+                rows.push((number, ts.and_then(|ts| self.candle(ts, row))));
+            }
+        }
+        collect_rows(path, "row", rows)
+    }
+
+    // Zoned timestamps are instants; naive ones and dates are wall-clock times in `timezone`
+    fn parquet_times(&self, array: &ArrayRef) -> Result<Vec<Result<DateTime<Utc>, String>>> {
+        let missing = || format!("missing `{}`", self.columns.time);
+        let times = match array.data_type() {
+            DataType::Timestamp(_, zone) => {
+                let millis = cast(
+                    array,
+                    &DataType::Timestamp(TimeUnit::Millisecond, zone.clone()),
+                )?;
+                millis
+                    .as_primitive::<TimestampMillisecondType>()
+                    .iter()
+                    .map(|ms| {
+                        let ms = ms.ok_or_else(missing)?;
+                        let ts = DateTime::from_timestamp_millis(ms)
+                            .ok_or_else(|| format!("invalid time {ms}"))?;
+                        match zone {
+                            Some(_) => Ok(ts),
+                            None => self.local_time(ts.naive_utc()),
+                        }
+                    })
+                    .collect()
+            }
+            DataType::Date32 | DataType::Date64 => cast(array, &DataType::Date32)?
+                .as_primitive::<Date32Type>()
+                .iter()
+                .map(|days| {
+                    let days = days.ok_or_else(missing)?;
+                    let midnight =
+                        date32_to_datetime(days).ok_or_else(|| format!("invalid date {days}"))?;
+                    self.local_time(midnight)
+                })
+                .collect(),
+            _ => cast(array, &DataType::Utf8)?
+                .as_string::<i32>()
+                .iter()
+                .map(|t| match t.map(str::trim).filter(|t| !t.is_empty()) {
+                    Some(t) => self.parse_time(t),
+                    None => Err(missing()),
+                })
+                .collect(),
+        };
+        Ok(times)
+    }
+
+    fn parse_time(&self, text: &str) -> Result<DateTime<Utc>, String> {
+        let invalid = || format!("invalid time `{text}`");
+        match &self.date_format {
+            DateFormat::Unix => text
+                .parse()
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                .ok_or_else(invalid),
+            DateFormat::UnixMs => text
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_millis)
+                .ok_or_else(invalid),
+            DateFormat::Rfc3339 => DateTime::parse_from_rfc3339(text)
+                .map(|t| t.to_utc())
+                .map_err(|_| invalid()),
+            DateFormat::Pattern(pattern) => {
+                // an offset in the pattern wins over the configured timezone
+                if let Ok(t) = DateTime::parse_from_str(text, pattern) {
+                    return Ok(t.to_utc());
+                }
+                if let Ok(naive) = NaiveDateTime::parse_from_str(text, pattern) {
+                    return self.local_time(naive);
+                }
+                match NaiveDate::parse_from_str(text, pattern) {
+                    Ok(day) => self.local_time(day.and_time(NaiveTime::MIN)),
+                    Err(_) => Err(format!("time `{text}` does not match `{pattern}`")),
+                }
+            }
+        }
+    }
+
+    fn local_time(&self, naive: NaiveDateTime) -> Result<DateTime<Utc>, String> {
+        // ambiguous times at a DST change take the earlier instant
+        self.timezone
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.to_utc())
+            .ok_or_else(|| format!("time {naive} does not exist in {}", self.timezone))
+    }
+
+    fn candle(&self, ts: DateTime<Utc>, row: RawRow<'_>) -> Result<MarketCandle, String> {
+        let columns = &self.columns;
+        let open = parse_price(&columns.open, row.open)?;
+        let high = parse_price(&columns.high, row.high)?;
+        let low = parse_price(&columns.low, row.low)?;
+        let close = parse_price(&columns.close, row.close)?;
+        let volume = match (&columns.volume, row.volume) {
+            (Some(name), Some(value)) => Some(parse_volume(name, value)?),
+            _ => None,
+        };
+        let unadjusted_close = match (&columns.unadjusted_close, row.unadjusted_close) {
+            (Some(name), Some(value)) => Some(parse_price(name, Some(value))?),
+            _ => None,
+        };
+
+        if high < low {
+            return Err(format!("high {high} is below low {low}"));
+        }
+        if high < open.max(close) {
+            return Err(format!("high {high} is below open {open} or close {close}"));
+        }
+        if low > open.min(close) {
+            return Err(format!("low {low} is above open {open} or close {close}"));
+        }
+        Ok(MarketCandle {
+            ts,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            unadjusted_close,
+        })
+    }
+}
+
+fn value(array: &StringArray, i: usize) -> Option<&str> {
+    (!array.is_null(i))
+        .then(|| array.value(i).trim())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_price(column: &str, value: Option<&str>) -> Result<Decimal, String> {
+    let value = value.ok_or_else(|| format!("missing `{column}`"))?;
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .map_err(|_| format!("invalid `{column}` value `{value}`"))
+}
+
+fn parse_volume(column: &str, value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .ok()
+        .or_else(|| {
+            // float columns write whole volumes as `1200.0`
+            Decimal::from_str(value)
+                .ok()
+                .filter(|v| v.fract().is_zero())
+                .and_then(|v| v.to_u64())
+        })
+        .ok_or_else(|| format!("invalid `{column}` value `{value}`"))
+}
+
+fn missing_column(column: &str, path: &Path) -> anyhow::Error {
+    MissingColumn {
+        column: column.to_string(),
+        path: path.display().to_string(),
+    }
+    .into()
+}
+
+// Rows must come in time order; every bad one is reported by its `unit` number
+fn collect_rows(
+    path: &Path,
+    unit: &str,
+    rows: Vec<(u64, Result<MarketCandle, String>)>,
+) -> Result<Vec<MarketCandle>> {
+    let mut candles: Vec<MarketCandle> = Vec::with_capacity(rows.len());
+    let mut malformed = Vec::new();
+    for (number, row) in rows {
+        let row = row.and_then(|candle| match candles.last() {
+            Some(prev) if prev.ts >= candle.ts => Err(format!(
+                "time {} is not after the previous row's {}",
+                candle.ts, prev.ts
+            )),
+            _ => Ok(candle),
+        });
+        match row {
+            Ok(candle) => candles.push(candle),
+            Err(e) => malformed.push(format!("{unit} {number}: {e}")),
+        }
+    }
+
+    if !malformed.is_empty() {
+        return Err(MalformedRows {
+            path: path.display().to_string(),
+            count: malformed.len(),
+            rows: malformed
+                .into_iter()
+                .take(MAX_REPORTED_ROWS)
+                .collect::<Vec<_>>()
+                .join("; "),
+        }
+        .into());
+    }
+    Ok(candles)
+}
+
+type ParsedFile = (SystemTime, Arc<Vec<MarketCandle>>);
+
+// Per-symbol `<TICKER>.csv` or `<TICKER>.parquet` files under `dir`, all holding bars of one interval
+#[derive(Debug)]
+pub struct FileProvider {
+    dir: PathBuf,
+    interval: TickerInterval,
+    parser: Arc<RowParser>,
+    // parsed files, reused until they change on disk
+    parsed: Mutex<HashMap<PathBuf, ParsedFile>>,
+}
+
+impl FileProvider {
+    pub fn new(config: &FilesConfig) -> Result<Self> {
+        let timezone = config.timezone.parse::<Tz>().map_err(|_| UnknownTimezone {
+            name: config.timezone.clone(),
+        })?;
+        let delimiter = u8::try_from(config.delimiter)
+            .ok()
+            .filter(u8::is_ascii)
+            .ok_or_else(|| anyhow!("Delimiter must be an ASCII character: {}", config.delimiter))?;
+
+        Ok(Self {
+            dir: PathBuf::from(&config.dir),
+            interval: config.interval,
+            parser: Arc::new(RowParser {
+                date_format: DateFormat::new(&config.date_format),
+                timezone,
+                delimiter,
+                columns: config.columns.clone(),
+            }),
+            parsed: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn find_file(&self, ticker: &str) -> Result<(PathBuf, SystemTime)> {
+        let symbol = symbol_path_name(ticker);
+        for extension in ["csv", "parquet"] {
+            let path = self.dir.join(format!("{symbol}.{extension}"));
+            if let Ok(metadata) = fs::metadata(&path).await {
+                if metadata.is_file() {
+                    return Ok((path, metadata.modified()?));
+                }
+            }
+        }
+        Err(NotFound {
+            ticker: ticker.to_string(),
+            dir: self.dir.display().to_string(),
+        }
+        .into())
+    }
+
+    async fn load(&self, ticker: &str) -> Result<Arc<Vec<MarketCandle>>> {
+        let (path, modified) = self.find_file(ticker).await?;
+        if let Some((at, candles)) = self.parsed.lock().unwrap().get(&path) {
+            if *at == modified {
+                return Ok(candles.clone());
+            }
+        }
+
+        let parser = self.parser.clone();
+        let read_path = path.clone();
+        let candles = Arc::new(spawn_blocking(move || parser.read(&read_path)).await??);
+        info!(path = %path.display(), candles = candles.len(), "Dataset file loaded");
+        self.parsed
+            .lock()
+            .unwrap()
+            .insert(path, (modified, candles.clone()));
+        Ok(candles)
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for FileProvider {
+    fn name(&self) -> &str {
+        "files"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![self.interval],
+            supports_actions: false,
+            timezone: self.parser.timezone,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        if interval != self.interval {
+            return Err(IntervalMismatch {
+                available: self.interval.to_string(),
+                requested: interval.to_string(),
+            }
+            .into());
+        }
+
+        let capabilities = self.capabilities();
+        let candles = self.load(ticker).await?;
+        Ok(MarketHistory {
+            candles: candles
+                .iter()
+                .filter(|c| (start..=end).contains(&capabilities.day_of(&c.ts)))
+                .cloned()
+                .collect(),
+            source: Some(self.name().to_string()),
+            ..MarketHistory::default()
+        })
+    }
+
+    // A missing or malformed file is a bad request, not an outage
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        !e.chain().any(|cause| cause.is::<DatasetError>())
+    }
+}
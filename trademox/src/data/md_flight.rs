@@ -0,0 +1,147 @@
+use crate::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use crate::errors::md_errors::SharedFetchError;
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use futures_util::future::{BoxFuture, FutureExt, Shared, WeakShared};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FlightKey {
+    ticker: String,
+    interval: TickerInterval,
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+type FlightFuture = BoxFuture<'static, Result<Arc<MarketHistory>, Arc<anyhow::Error>>>;
+type Flight = Shared<FlightFuture>;
+
+// Concurrent identical fetches await one upstream call; nothing is kept once it finishes.
+// Flights are held by their callers only, so one whose callers were all cancelled is dropped, never resumed
+pub struct SingleFlightProvider {
+    inner: Arc<dyn MarketDataProvider>,
+    in_flight: Mutex<HashMap<FlightKey, WeakShared<FlightFuture>>>,
+    coalesced: AtomicU64,
+}
+
+impl std::fmt::Debug for SingleFlightProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SingleFlightProvider")
+            .field("inner", &self.inner)
+            .field("coalesced", &self.coalesced)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SingleFlightProvider {
+    pub fn new(inner: Arc<dyn MarketDataProvider>) -> Self {
+        Self {
+            inner,
+            in_flight: Mutex::new(HashMap::new()),
+            coalesced: AtomicU64::new(0),
+        }
+    }
+
+    // Callers that joined a fetch already in flight
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    fn flight(&self, key: &FlightKey) -> Flight {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        in_flight.retain(|_, flight| flight.upgrade().is_some());
+        if let Some(flight) = in_flight.get(key).and_then(WeakShared::upgrade) {
+            self.coalesced.fetch_add(1, Ordering::Relaxed);
+            debug!(ticker = %key.ticker, start = %key.start, end = %key.end, "Joined fetch in flight");
+            return flight;
+        }
+
+        let inner = self.inner.clone();
+        let FlightKey {
+            ticker,
+            interval,
+            start,
+            end,
+        } = key.clone();
+        let flight = async move {
+            inner
+                .fetch_history_data(&ticker, interval, start, end)
+                .await
+                .map(Arc::new)
+                .map_err(Arc::new)
+        }
+        .boxed()
+        .shared();
+        if let Some(weak) = flight.downgrade() {
+            in_flight.insert(key.clone(), weak);
+        }
+        flight
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for SingleFlightProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        let key = FlightKey {
+            ticker: ticker.to_string(),
+            interval,
+            start,
+            end,
+        };
+        let flight = self.flight(&key);
+        let result = flight.clone().await;
+
+        // the next caller starts a fresh fetch
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .and_then(WeakShared::upgrade)
+            .is_some_and(|f| f.ptr_eq(&flight))
+        {
+            in_flight.remove(&key);
+        }
+        drop(in_flight);
+
+        match result {
+            Ok(history) => Ok(history.as_ref().clone()),
+            Err(e) => Err(SharedFetchError(e).into()),
+        }
+    }
+
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        match e.downcast_ref::<SharedFetchError>() {
+            Some(SharedFetchError(shared)) => self.inner.is_outage(shared),
+            None => self.inner.is_outage(e),
+        }
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        self.inner.fetch_latest_bars(ticker, interval, since).await
+    }
+}
@@ -0,0 +1,54 @@
+use crate::configs::yaml_config::RateLimitConfig;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
+
+// Token bucket: `burst` calls at once, then `requests_per_sec` on average
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    // held across the wait, so callers are served in arrival order
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_sec: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: requests_per_sec,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    // None when the limit is turned off
+    pub fn from_config(config: &RateLimitConfig) -> Option<Self> {
+        (config.requests_per_sec > 0.0).then(|| Self::new(config.requests_per_sec, config.burst))
+    }
+
+    pub async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.refilled_at).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.refilled_at = now;
+
+        if bucket.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate);
+            sleep(wait).await;
+            bucket.tokens = 1.0;
+            bucket.refilled_at = Instant::now();
+        }
+        bucket.tokens -= 1.0;
+    }
+}
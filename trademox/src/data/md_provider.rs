@@ -0,0 +1,164 @@
+use crate::configs::yaml_config::ProviderConfig;
+use crate::data::md_files::FileProvider;
+use crate::data::md_ranges::DayRange;
+use crate::data::yfinance_data::YFinanceData;
+use crate::errors::md_errors::ProviderError::UnknownProvider;
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketCandle {
+    pub ts: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Option<u64>,
+    // raw close when `close` is adjusted for splits and dividends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unadjusted_close: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MarketAction {
+    Dividend {
+        ts: DateTime<Utc>,
+        amount: Decimal,
+    },
+    Split {
+        ts: DateTime<Utc>,
+        numerator: u32,
+        denominator: u32,
+    },
+}
+
+impl MarketAction {
+    pub fn ts(&self) -> DateTime<Utc> {
+        match self {
+            MarketAction::Dividend { ts, .. } | MarketAction::Split { ts, .. } => *ts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketHistory {
+    pub candles: Vec<MarketCandle>,
+    pub actions: Vec<MarketAction>,
+    // served from a local copy because the upstream call failed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    // data source that answered, e.g. `yfinance` or `store`; `+` joins them when several did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    // day ranges of the request with no bars to serve, e.g. parts a stale copy never held
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<DayRange>,
+}
+
+impl MarketHistory {
+    // Later bars win when both sides hold the same timestamp
+    pub fn merge(self, other: MarketHistory) -> MarketHistory {
+        let candles = self
+            .candles
+            .into_iter()
+            .chain(other.candles)
+            .map(|c| (c.ts, c))
+            .collect::<BTreeMap<_, _>>();
+        let actions = self
+            .actions
+            .into_iter()
+            .chain(other.actions)
+            .map(|a| (a.ts(), a))
+            .collect::<BTreeMap<_, _>>();
+        let gaps = self
+            .gaps
+            .into_iter()
+            .chain(other.gaps)
+            .collect::<BTreeSet<_>>();
+
+        MarketHistory {
+            candles: candles.into_values().collect(),
+            actions: actions.into_values().collect(),
+            stale: self.stale || other.stale,
+            source: join_sources([self.source, other.source]),
+            gaps: gaps.into_iter().collect(),
+        }
+    }
+}
+
+pub fn join_sources(sources: impl IntoIterator<Item = Option<String>>) -> Option<String> {
+    let sources = sources
+        .into_iter()
+        .flatten()
+        .flat_map(|s| s.split('+').map(str::to_string).collect::<Vec<_>>())
+        .collect::<BTreeSet<_>>();
+    (!sources.is_empty()).then(|| sources.into_iter().collect::<Vec<_>>().join("+"))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    pub intervals: Vec<TickerInterval>,
+    pub supports_actions: bool,
+    // requested days are matched in this timezone
+    pub timezone: Tz,
+}
+
+impl ProviderCapabilities {
+    pub fn day_of(&self, ts: &DateTime<Utc>) -> NaiveDate {
+        ts.with_timezone(&self.timezone).date_naive()
+    }
+}
+
+// Source of historical market data, selected by `provider.name` in config.yaml
+#[async_trait]
+pub trait MarketDataProvider: Debug + Send + Sync {
+    fn name(&self) -> &str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory>;
+
+    // Whether `e` means the upstream is unavailable, as opposed to a bad request such as an unknown ticker
+    fn is_outage(&self, _e: &anyhow::Error) -> bool {
+        true
+    }
+
+    // Bars from `since` up to now for live polling; caches pass this straight through
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        let today = Utc::now().date_naive();
+        let history = self
+            .fetch_history_data(ticker, interval, since.min(today), today)
+            .await?;
+        Ok(history.candles)
+    }
+}
+
+pub fn create_provider(config: &ProviderConfig) -> Result<Arc<dyn MarketDataProvider>> {
+    match config.name.as_str() {
+        "yfinance" => Ok(Arc::new(YFinanceData::from_config(config)?)),
+        "files" => Ok(Arc::new(FileProvider::new(&config.files)?)),
+        name => Err(UnknownProvider {
+            name: name.to_string(),
+        }
+        .into()),
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{Duration, NaiveDate, Utc};
+
+pub type DayRange = (NaiveDate, NaiveDate);
+
+// Parts of the inclusive [start, end] day range not covered by any of `covered`
+pub fn uncovered_ranges(covered: &[DayRange], start: NaiveDate, end: NaiveDate) -> Vec<DayRange> {
+    let mut covered = covered.to_vec();
+    covered.sort_by_key(|(range_start, _)| *range_start);
+
+    let mut missing = Vec::new();
+    let mut cursor = start;
+    for (range_start, range_end) in covered {
+        if cursor > end {
+            break;
+        }
+        if range_end < cursor {
+            continue;
+        }
+        if range_start > cursor {
+            missing.push((cursor, (range_start - Duration::days(1)).min(end)));
+        }
+        cursor = cursor.max(range_end + Duration::days(1));
+    }
+    if cursor <= end {
+        missing.push((cursor, end));
+    }
+    missing
+}
+
+// Back-to-back inclusive ranges of at most `chunk_days` days covering [start, end] in date order
+pub fn chunk_ranges(start: NaiveDate, end: NaiveDate, chunk_days: i64) -> Vec<DayRange> {
+    let step = Duration::days(chunk_days.max(1));
+    let mut chunks = Vec::new();
+    let mut current_start = start;
+    while current_start <= end {
+        let chunk_end = (current_start + step - Duration::days(1)).min(end);
+        chunks.push((current_start, chunk_end));
+        current_start = chunk_end + Duration::days(1);
+    }
+    chunks
+}
+
+// Splits [start, end] into settled days and days from yesterday onwards, which may still be revised upstream
+pub fn split_recent(start: NaiveDate, end: NaiveDate) -> (Option<DayRange>, Option<DayRange>) {
+    let recent_from = Utc::now().date_naive() - Duration::days(1);
+    let settled = (start < recent_from).then(|| (start, end.min(recent_from - Duration::days(1))));
+    let recent = (end >= recent_from).then(|| (start.max(recent_from), end));
+    (settled, recent)
+}
@@ -0,0 +1,68 @@
+use crate::data::md_provider::MarketCandle;
+use crate::requests::tk_resample::TickerResample;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+
+// Aggregates sorted provider bars into `resample` bars, stamped with the time of their first bar
+#[derive(Debug, Clone, Copy)]
+pub struct Resampler {
+    resample: TickerResample,
+    week_anchor: Weekday,
+    // N-day bars are counted from this day
+    origin: NaiveDate,
+}
+
+impl Resampler {
+    pub fn new(resample: TickerResample, week_anchor: Weekday, origin: NaiveDate) -> Self {
+        Self {
+            resample,
+            week_anchor,
+            origin,
+        }
+    }
+
+    // Bars sharing a bucket go into the same resampled bar
+    pub fn bucket(&self, ts: DateTime<Utc>) -> i64 {
+        let day = ts.date_naive();
+        match self.resample {
+            TickerResample::None => ts.timestamp(),
+            TickerResample::Minutes(n) => ts.timestamp().div_euclid(i64::from(n) * 60),
+            TickerResample::Days(n) => (day - self.origin).num_days().div_euclid(i64::from(n)),
+            TickerResample::Week => {
+                let offset = (day.weekday().num_days_from_monday() + 7
+                    - self.week_anchor.num_days_from_monday())
+                    % 7;
+                i64::from((day - Duration::days(i64::from(offset))).num_days_from_ce())
+            }
+            TickerResample::Month => i64::from(day.year()) * 12 + i64::from(day.month0()),
+            TickerResample::Quarter => i64::from(day.year()) * 4 + i64::from(day.month0() / 3),
+        }
+    }
+
+    pub fn resample(&self, candles: Vec<MarketCandle>) -> Vec<MarketCandle> {
+        if self.resample == TickerResample::None {
+            return candles;
+        }
+
+        let mut bars: Vec<(i64, MarketCandle)> = Vec::new();
+        for candle in candles {
+            let bucket = self.bucket(candle.ts);
+            match bars.last_mut() {
+                Some((last, bar)) if *last == bucket => merge(bar, candle),
+                _ => bars.push((bucket, candle)),
+            }
+        }
+        bars.into_iter().map(|(_, bar)| bar).collect()
+    }
+}
+
+// First open, highest high, lowest low, last close, summed volume
+fn merge(bar: &mut MarketCandle, next: MarketCandle) {
+    bar.high = bar.high.max(next.high);
+    bar.low = bar.low.min(next.low);
+    bar.close = next.close;
+    bar.unadjusted_close = next.unadjusted_close;
+    bar.volume = match (bar.volume, next.volume) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+}
@@ -0,0 +1,76 @@
+use crate::configs::yaml_config::RetryConfig;
+use crate::errors::md_errors::ProviderError::RetriesExhausted;
+use anyhow::Result;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    seed: RandomState,
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.base_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+            jitter: config.jitter,
+            seed: RandomState::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Exponential from `base_delay`, capped at `max_delay`; jitter picks a point in its upper half
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let fraction = (self.seed.hash_one((retry, std::time::Instant::now())) % 1000) as u32;
+        delay / 2 + (delay / 2) * fraction / 1000
+    }
+
+    // Runs `op` until it succeeds, fails with an error `is_retryable` rejects, or retries run out
+    pub async fn run<T, F, Fut>(
+        &self,
+        mut op: F,
+        is_retryable: fn(&anyhow::Error) -> bool,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            match op().await {
+                Ok(value) => return Ok(value),
+                Err(e) if retry < self.max_retries && is_retryable(&e) => {
+                    let delay = self.delay(retry);
+                    warn!(error = %e, retry = retry + 1, ?delay, "Upstream call failed, retrying");
+                    sleep(delay).await;
+                    retry += 1;
+                }
+                // only a retryable error that outlasted every retry says so; others pass through as is
+                Err(e) if is_retryable(&e) => {
+                    return Err(e.context(RetriesExhausted {
+                        attempts: retry + 1,
+                    }))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use crate::configs::yaml_config::StoreMode;
+use crate::data::md_provider::{
+    MarketAction, MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use crate::data::md_ranges::{split_recent, uncovered_ranges, DayRange};
+use crate::errors::md_errors::ProviderError::{self, NoStoredHistory};
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, to_vec};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredHistory {
+    covered: Vec<DayRange>,
+    candles: Vec<MarketCandle>,
+    actions: Vec<MarketAction>,
+}
+
+// One JSON file per symbol and interval: `<dir>/<TICKER>/<interval>.json`
+#[derive(Debug)]
+pub struct LocalStore {
+    dir: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl LocalStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    fn file_path(&self, ticker: &str, interval: TickerInterval) -> PathBuf {
+        self.dir
+            .join(symbol_path_name(ticker))
+            .join(format!("{}.json", interval.as_str()))
+    }
+
+    async fn load(&self, ticker: &str, interval: TickerInterval) -> Result<StoredHistory> {
+        let path = self.file_path(ticker, interval);
+        match fs::read(&path).await {
+            Ok(bytes) => from_slice(&bytes)
+                .with_context(|| format!("Corrupted store file: {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(StoredHistory::default()),
+            Err(e) => Err(e).with_context(|| format!("Reading {} failed", path.display())),
+        }
+    }
+
+    // Bars on [start, end] as days in `timezone`, the one the bars were fetched with
+    pub async fn read(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+        timezone: Tz,
+    ) -> Result<(MarketHistory, Vec<DayRange>)> {
+        let stored = self.load(ticker, interval).await?;
+        let missing = uncovered_ranges(&stored.covered, start, end);
+
+        let in_range =
+            |ts: &DateTime<Utc>| (start..=end).contains(&ts.with_timezone(&timezone).date_naive());
+        let candles = stored
+            .candles
+            .into_iter()
+            .filter(|c| in_range(&c.ts))
+            .collect::<Vec<_>>();
+        let history = MarketHistory {
+            source: (!candles.is_empty()).then(|| self.name().to_string()),
+            candles,
+            actions: stored
+                .actions
+                .into_iter()
+                .filter(|a| in_range(&a.ts()))
+                .collect(),
+            stale: false,
+            gaps: Vec::new(),
+        };
+        Ok((history, missing))
+    }
+
+    // The stored bars, failing when there are none
+    pub async fn read_stored(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+        timezone: Tz,
+    ) -> Result<MarketHistory> {
+        let (history, missing) = self.read(ticker, interval, start, end, timezone).await?;
+        if history.candles.is_empty() {
+            return Err(NoStoredHistory {
+                ticker: ticker.to_string(),
+                interval,
+            }
+            .into());
+        }
+        if !missing.is_empty() {
+            warn!(ticker, %interval, ?missing, "Local store does not cover the full range");
+        }
+        Ok(MarketHistory {
+            gaps: missing,
+            ..history
+        })
+    }
+
+    pub async fn write(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+        history: &MarketHistory,
+    ) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let mut stored = self.load(ticker, interval).await?;
+
+        let merged = MarketHistory {
+            candles: stored.candles,
+            actions: stored.actions,
+            ..MarketHistory::default()
+        }
+        .merge(history.clone());
+
+        // recent days are written but stay uncovered so they get fetched again
+        if let (Some(settled), _) = split_recent(start, end) {
+            stored.covered.push(settled);
+        }
+
+        let stored = StoredHistory {
+            covered: merge_ranges(stored.covered),
+            candles: merged.candles,
+            actions: merged.actions,
+        };
+
+        let path = self.file_path(ticker, interval);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        // a crash mid-write leaves the previous file in place
+        let tmp_path = path.with_extension("json.tmp");
+        let mut tmp_file = fs::File::create(&tmp_path).await?;
+        tmp_file.write_all(&to_vec(&stored)?).await?;
+        tmp_file.sync_all().await?;
+        fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("Writing {} failed", path.display()))?;
+
+        info!(ticker, %interval, %start, %end, "History written to local store");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for LocalStore {
+    fn name(&self) -> &str {
+        "local_store"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: TickerInterval::ALL.to_vec(),
+            supports_actions: true,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.read_stored(ticker, interval, start, end, self.capabilities().timezone)
+            .await
+    }
+
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        !e.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<ProviderError>(),
+                Some(NoStoredHistory { .. })
+            )
+        })
+    }
+}
+
+// Network provider backed by the local store, in the order given by `mode`
+#[derive(Debug)]
+pub struct StoredProvider {
+    network: Arc<dyn MarketDataProvider>,
+    store: Arc<LocalStore>,
+    mode: StoreMode,
+}
+
+impl StoredProvider {
+    pub fn new(
+        network: Arc<dyn MarketDataProvider>,
+        store: Arc<LocalStore>,
+        mode: StoreMode,
+    ) -> Self {
+        Self {
+            network,
+            store,
+            mode,
+        }
+    }
+
+    async fn fetch_and_store(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        let history = self
+            .network
+            .fetch_history_data(ticker, interval, start, end)
+            .await?;
+        if let Err(e) = self
+            .store
+            .write(ticker, interval, start, end, &history)
+            .await
+        {
+            warn!(error = %e, ticker, "Local store write failed");
+        }
+        Ok(history)
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for StoredProvider {
+    fn name(&self) -> &str {
+        self.network.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.network.capabilities()
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        // stored bars are matched on the days the network provider uses
+        let timezone = self.network.capabilities().timezone;
+        match self.mode {
+            StoreMode::OfflineOnly => {
+                self.store
+                    .read_stored(ticker, interval, start, end, timezone)
+                    .await
+            }
+            StoreMode::CacheFirst => {
+                let (mut history, missing) = self
+                    .store
+                    .read(ticker, interval, start, end, timezone)
+                    .await?;
+                for (miss_start, miss_end) in missing {
+                    match self
+                        .fetch_and_store(ticker, interval, miss_start, miss_end)
+                        .await
+                    {
+                        Ok(fetched) => history = history.merge(fetched),
+                        Err(e) if history.candles.is_empty() || !self.network.is_outage(&e) => {
+                            return Err(e)
+                        }
+                        Err(e) => {
+                            warn!(error = %e, ticker, "Network fetch failed, serving stored bars as stale");
+                            history.stale = true;
+                            history.gaps.push((miss_start, miss_end));
+                        }
+                    }
+                }
+                Ok(history)
+            }
+            StoreMode::NetworkFirst => {
+                match self.fetch_and_store(ticker, interval, start, end).await {
+                    Ok(history) => Ok(history),
+                    Err(e) if !self.network.is_outage(&e) => Err(e),
+                    Err(e) => {
+                        warn!(error = %e, ticker, "Network fetch failed, reading local store");
+                        self.store
+                            .read_stored(ticker, interval, start, end, timezone)
+                            .await
+                            .map(|history| MarketHistory {
+                                stale: true,
+                                ..history
+                            })
+                            .map_err(|_| e)
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        match self.mode {
+            StoreMode::OfflineOnly => self.store.is_outage(e),
+            StoreMode::CacheFirst | StoreMode::NetworkFirst => self.network.is_outage(e),
+        }
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        match self.mode {
+            StoreMode::OfflineOnly => self.store.fetch_latest_bars(ticker, interval, since).await,
+            StoreMode::CacheFirst | StoreMode::NetworkFirst => {
+                self.network
+                    .fetch_latest_bars(ticker, interval, since)
+                    .await
+            }
+        }
+    }
+}
+
+// Percent-encodes all but ASCII letters, digits and `-`, so `BRK.B` and `BRK_B` never share a path;
+// used for store directories and dataset file names alike
+pub fn symbol_path_name(ticker: &str) -> String {
+    ticker
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b == b'-' {
+                (b as char).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect()
+}
+
+fn merge_ranges(mut ranges: Vec<DayRange>) -> Vec<DayRange> {
+    ranges.sort();
+    let mut merged: Vec<DayRange> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end + Duration::days(1) => {
+                *last_end = (*last_end).max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
@@ -1,2 +1,14 @@
+pub mod md_breaker;
+pub mod md_cache;
+pub mod md_chunks;
+pub mod md_failover;
+pub mod md_files;
+pub mod md_flight;
+pub mod md_limiter;
+pub mod md_provider;
+pub mod md_ranges;
+pub mod md_resampler;
+pub mod md_retry;
+pub mod md_store;
 pub mod ticker_data;
 pub mod yfinance_data;
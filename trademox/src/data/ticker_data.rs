@@ -1,12 +1,17 @@
 use crate::app::app_box::AppBox;
-use crate::creator::tk_creator::TickerParams;
+use crate::creator::tk_creator::{TickerParams, TickerSnapshot};
+use crate::data::md_resampler::Resampler;
 use crate::errors::{tk_errors::TickerDateRangeError::InvalidDateRange, yf_error::YFinanceError};
+use crate::indicators::ind_calc::apply_indicators;
+use crate::indicators::ind_spec::IndicatorSpec;
+use crate::requests::tk_interval::TickerInterval;
 use crate::requests::tk_request::TickerRequest;
+use crate::requests::tk_resample::TickerResample;
 use anyhow::{anyhow, Context, Result};
-use chrono::NaiveDate;
+use chrono::{Duration, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TickerData;
 
 impl TickerData {
@@ -17,27 +22,135 @@ impl TickerData {
         &self,
         tk_request: &TickerRequest,
         app: &AppBox,
-    ) -> Result<Vec<TickerParams>> {
+    ) -> Result<TickerSnapshot> {
         let start = NaiveDate::parse_from_str(&tk_request.start, "%Y-%m-%d")
             .context("Invalid start date")?;
         let end =
             NaiveDate::parse_from_str(&tk_request.end, "%Y-%m-%d").context("Invalid end date")?;
+        let (interval, resample) = bar_size(tk_request)?;
+
+        // indicators need bars before `start` so the first candle already has values
+        let warmup_bars = warmup_bars(&tk_request.indicators);
+        let fetch_start = if warmup_bars > 0 {
+            let warm_start = start - Duration::days(resample.days_for_bars(warmup_bars, interval));
+            match interval.lookback_days() {
+                Some(days) => warm_start.max(Utc::now().date_naive() - Duration::days(days)),
+                None => warm_start,
+            }
+            .min(start)
+        } else {
+            start
+        };
 
-        let history_data = app
-            .yfinance_data
-            .fetch_history_data(&tk_request.ticker, start, end)
+        let history = app
+            .provider
+            .fetch_history_data(&tk_request.ticker, interval, fetch_start, end)
             .await
             .context(YFinanceError::FetchFailed {
                 ticker: tk_request.ticker.to_string(),
-                source: anyhow!("Context"),
+                source: anyhow!("Provider: {}", app.provider.name()),
             })?;
 
-        let ticker_data = app
+        let resampler = Resampler::new(resample, app.resample.week_anchor, start);
+        let mut ticker_data = app
             .ticker_box
-            .create_ticker_data(history_data, start, end)
+            .create_ticker_data(resampler.resample(history.candles), start, end)
             .await
             .context(InvalidDateRange { start, end })?;
+        if !tk_request.indicators.is_empty() {
+            apply_indicators(&mut ticker_data, &tk_request.indicators);
+            // a bar that straddles `start` is kept whole
+            let first_bucket = resampler.bucket(start.and_time(NaiveTime::MIN).and_utc());
+            ticker_data.retain(|c| resampler.bucket(c.time) >= first_bucket);
+        }
+        let ticker_events = app
+            .ticker_box
+            .create_ticker_events(history.actions, start, end);
+
+        Ok(TickerSnapshot {
+            candles: ticker_data,
+            events: ticker_events,
+            stale: history.stale,
+            source: history.source,
+            // warm-up days are not part of the answer
+            gaps: history
+                .gaps
+                .into_iter()
+                .filter(|(_, gap_end)| *gap_end >= start)
+                .map(|(gap_start, gap_end)| (gap_start.max(start), gap_end))
+                .collect(),
+        })
+    }
+
+    // Latest bars from `since` for live polling, resampled like the history.
+    // Indicators are computed with the `history` bars before them as warm-up
+    pub async fn fetch_latest(
+        &self,
+        tk_request: &TickerRequest,
+        app: &AppBox,
+        since: NaiveDate,
+        history: &[TickerParams],
+    ) -> Result<Vec<TickerParams>> {
+        let start = NaiveDate::parse_from_str(&tk_request.start, "%Y-%m-%d")
+            .context("Invalid start date")?;
+        let (interval, resample) = bar_size(tk_request)?;
 
-        Ok(ticker_data)
+        let candles = app
+            .provider
+            .fetch_latest_bars(&tk_request.ticker, interval, since)
+            .await
+            .context(YFinanceError::FetchFailed {
+                ticker: tk_request.ticker.to_string(),
+                source: anyhow!("Provider: {}", app.provider.name()),
+            })?;
+        let resampler = Resampler::new(resample, app.resample.week_anchor, start);
+        let today = Utc::now().date_naive();
+        let bars = app
+            .ticker_box
+            .create_ticker_data(resampler.resample(candles), since.min(today), today)
+            .await?;
+        if tk_request.indicators.is_empty() {
+            return Ok(bars);
+        }
+
+        let first = bars.first().map(|c| c.time);
+        let earlier = history
+            .iter()
+            .filter(|c| first.is_none_or(|t| c.time < t))
+            .collect::<Vec<_>>();
+        let warmup = &earlier[earlier
+            .len()
+            .saturating_sub(warmup_bars(&tk_request.indicators))..];
+        let mut series = warmup
+            .iter()
+            .map(|c| (*c).clone())
+            .chain(bars)
+            .collect::<Vec<_>>();
+        apply_indicators(&mut series, &tk_request.indicators);
+        Ok(series.split_off(warmup.len()))
     }
 }
+
+// Bars the indicators need before the first one that gets values
+pub fn warmup_bars(indicators: &[IndicatorSpec]) -> usize {
+    indicators
+        .iter()
+        .map(IndicatorSpec::warmup_bars)
+        .max()
+        .unwrap_or(0)
+}
+
+fn bar_size(tk_request: &TickerRequest) -> Result<(TickerInterval, TickerResample)> {
+    let interval = if tk_request.interval.is_empty() {
+        TickerInterval::default()
+    } else {
+        tk_request.interval.parse::<TickerInterval>()?
+    };
+    let resample = if tk_request.resample.is_empty() {
+        TickerResample::default()
+    } else {
+        tk_request.resample.parse::<TickerResample>()?
+    };
+    resample.check_source(interval)?;
+    Ok((interval, resample))
+}
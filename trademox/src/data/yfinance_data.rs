@@ -1,65 +1,282 @@
+use crate::configs::yaml_config::ProviderConfig;
+use crate::data::md_chunks::{day_gaps, fetch_chunked};
+use crate::data::md_limiter::RateLimiter;
+use crate::data::md_provider::{
+    MarketAction, MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use crate::data::md_retry::RetryPolicy;
+use crate::requests::tk_interval::TickerInterval;
 use anyhow::{Context, Result};
-use chrono::{Duration, NaiveDate};
-use serde::{Deserialize, Serialize};
-use yfinance_rs::Interval::D1;
-use yfinance_rs::{Candle, Range, Ticker, YfClient};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tracing::warn;
+use yfinance_rs::{
+    Action, Candle, HistoryResponse, Interval, Range, RetryConfig, Ticker, YfClient, YfError,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct YFinanceData;
+// Longest run of days a market stays closed, a long weekend around a holiday
+const MAX_CLOSED_DAYS: i64 = 4;
+
+// One long-lived client, so every chunk and request reuses its connections
+#[derive(Debug, Clone)]
+pub struct YFinanceData {
+    client: YfClient,
+    chunk_concurrency: usize,
+    retry: RetryPolicy,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Default for YFinanceData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl YFinanceData {
     pub fn new() -> Self {
-        Self
+        Self::with_client(YfClient::default(), &ProviderConfig::default())
     }
 
-    pub async fn fetch_history_data(
-        &self,
-        ticker: &String,
-        start: NaiveDate,
-        end: NaiveDate,
-    ) -> Result<Vec<Candle>> {
-        let mut all_candles = Vec::new();
-        let mut current_start = start;
-
-        while current_start < end {
-            let chunk_end = (current_start + Duration::days(365)).min(end);
-
-            let chunk = self
-                .fetch_daily_chunk(ticker, current_start, chunk_end)
-                .await?;
-            all_candles.extend(chunk);
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let client = YfClient::builder()
+            .timeout(std::time::Duration::from_secs(config.timeout_secs))
+            .connect_timeout(std::time::Duration::from_secs(config.connect_timeout_secs))
+            .build()
+            .context("Error: Building the Yahoo Finance client failed")?;
+        Ok(Self::with_client(client, config))
+    }
 
-            current_start = chunk_end + Duration::days(1);
+    pub fn with_client(client: YfClient, config: &ProviderConfig) -> Self {
+        Self {
+            client,
+            chunk_concurrency: config.chunk_concurrency.max(1),
+            retry: RetryPolicy::from(&config.retry),
+            limiter: RateLimiter::from_config(&config.rate_limit).map(Arc::new),
         }
-
-        Ok(all_candles)
     }
 
-    async fn fetch_daily_chunk(
+    async fn fetch_chunk(
         &self,
         ticker: &str,
-        start: NaiveDate,
-        end: NaiveDate,
-    ) -> Result<Vec<Candle>> {
-        let yf_client = YfClient::default();
-        let yf_ticker = Ticker::new(&yf_client, ticker);
+        interval: TickerInterval,
+        start_dt: DateTime<Utc>,
+        end_dt: DateTime<Utc>,
+    ) -> Result<HistoryResponse> {
+        self.retry
+            .run(
+                || async {
+                    if let Some(limiter) = &self.limiter {
+                        limiter.acquire().await;
+                    }
+                    self.fetch_chunk_once(ticker, interval, start_dt, end_dt)
+                        .await
+                },
+                is_retryable,
+            )
+            .await
+    }
 
-        let start_dt = start.and_hms_opt(0, 0, 0).unwrap().and_utc();
-        let end_dt = end.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    async fn fetch_chunk_once(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start_dt: DateTime<Utc>,
+        end_dt: DateTime<Utc>,
+    ) -> Result<HistoryResponse> {
+        let yf_ticker = Ticker::new(&self.client, ticker);
 
+        // retries are ours, so every attempt passes the rate limiter
+        let no_retry = RetryConfig {
+            enabled: false,
+            ..RetryConfig::default()
+        };
         let history_builder = yf_ticker
             .history_builder()
             .range(Range::Max)
-            .interval(D1)
+            .interval(to_yf_interval(interval))
             .auto_adjust(true)
             .prepost(false)
-            .actions(true);
+            .actions(true)
+            .retry_policy(Some(no_retry));
 
         let history_builder = history_builder.between(start_dt, end_dt);
 
         history_builder
-            .fetch()
+            .fetch_full()
             .await
             .context("Error: History builder is failed")
     }
 }
+
+// Rate limits, server errors, request timeouts and dropped connections are worth another try
+fn is_retryable(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<YfError>() {
+        Some(YfError::RateLimited { .. } | YfError::ServerError { .. }) => true,
+        Some(YfError::Status { status, .. }) => *status == 408,
+        Some(YfError::Http(http)) => http.is_timeout() || http.is_connect(),
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for YFinanceData {
+    fn name(&self) -> &str {
+        "yfinance"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: TickerInterval::ALL.to_vec(),
+            supports_actions: true,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        let start = clamp_to_lookback(interval, start);
+        let mut history = fetch_chunked(
+            start,
+            end,
+            interval.chunk_days(),
+            self.chunk_concurrency,
+            |(chunk_start, chunk_end)| async move {
+                // `between` excludes its end, so each chunk runs to the midnight after its last day
+                let mut chunk = self
+                    .fetch_chunk(
+                        ticker,
+                        interval,
+                        midnight(chunk_start),
+                        midnight(chunk_end + Duration::days(1)),
+                    )
+                    .await?;
+                Ok(MarketHistory {
+                    candles: take_market_candles(&mut chunk),
+                    actions: chunk
+                        .actions
+                        .into_iter()
+                        .filter_map(to_market_action)
+                        .collect(),
+                    stale: false,
+                    source: Some(self.name().to_string()),
+                    gaps: Vec::new(),
+                })
+            },
+        )
+        .await?;
+
+        // weekly and monthly bars are days apart by design
+        if matches!(interval, TickerInterval::OneDay) || interval.is_intraday() {
+            let gaps = day_gaps(&history.candles, start, end, MAX_CLOSED_DAYS);
+            for (gap_start, gap_end) in &gaps {
+                warn!(ticker, %interval, %gap_start, %gap_end, "No bars returned for days in range");
+            }
+            history.gaps.extend(gaps);
+            history.gaps.sort();
+            history.gaps.dedup();
+        }
+
+        Ok(history)
+    }
+
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        is_retryable(e)
+    }
+
+    // Up to now rather than midnight, so the forming bar of today is included
+    async fn fetch_latest_bars(
+        &self,
+        ticker: &str,
+        interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        let since = clamp_to_lookback(interval, since);
+        let mut chunk = self
+            .fetch_chunk(ticker, interval, midnight(since), Utc::now())
+            .await?;
+        Ok(take_market_candles(&mut chunk))
+    }
+}
+
+fn midnight(day: NaiveDate) -> DateTime<Utc> {
+    day.and_time(NaiveTime::MIN).and_utc()
+}
+
+// Pairs each candle with the raw close at the same index, when Yahoo sends one
+fn take_market_candles(chunk: &mut HistoryResponse) -> Vec<MarketCandle> {
+    let unadjusted_close = chunk
+        .unadjusted_close
+        .take()
+        .map(|closes| closes.into_iter().map(|c| c.amount()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    std::mem::take(&mut chunk.candles)
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| to_market_candle(c, unadjusted_close.get(i).copied()))
+        .collect()
+}
+
+fn to_market_candle(candle: Candle, unadjusted_close: Option<Decimal>) -> MarketCandle {
+    MarketCandle {
+        ts: candle.ts,
+        open: candle.open.amount(),
+        high: candle.high.amount(),
+        low: candle.low.amount(),
+        close: candle.close.amount(),
+        volume: candle.volume,
+        unadjusted_close,
+    }
+}
+
+fn to_market_action(action: Action) -> Option<MarketAction> {
+    match action {
+        Action::Dividend { ts, amount } => Some(MarketAction::Dividend {
+            ts,
+            amount: amount.amount(),
+        }),
+        Action::Split {
+            ts,
+            numerator,
+            denominator,
+        } => Some(MarketAction::Split {
+            ts,
+            numerator,
+            denominator,
+        }),
+        Action::CapitalGain { .. } => None,
+    }
+}
+
+fn to_yf_interval(interval: TickerInterval) -> Interval {
+    match interval {
+        TickerInterval::OneMinute => Interval::I1m,
+        TickerInterval::FiveMinutes => Interval::I5m,
+        TickerInterval::FifteenMinutes => Interval::I15m,
+        TickerInterval::ThirtyMinutes => Interval::I30m,
+        TickerInterval::OneHour => Interval::I1h,
+        TickerInterval::OneDay => Interval::D1,
+        TickerInterval::OneWeek => Interval::W1,
+        TickerInterval::OneMonth => Interval::M1,
+    }
+}
+
+fn clamp_to_lookback(interval: TickerInterval, start: NaiveDate) -> NaiveDate {
+    let Some(lookback) = interval.lookback_days() else {
+        return start;
+    };
+
+    let earliest = Utc::now().date_naive() - Duration::days(lookback);
+    if start < earliest {
+        warn!(%interval, %start, %earliest, "Start is outside the intraday lookback window");
+        return earliest;
+    }
+    start
+}
@@ -35,6 +35,86 @@ impl ApiError {
         }
     }
 
+    pub fn invalid_interval(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "InvalidInterval".to_string(),
+            message,
+            details: None,
+            field: Some("interval".to_string()),
+        }
+    }
+
+    pub fn invalid_pace(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "InvalidPace".to_string(),
+            message,
+            details: None,
+            field: Some("pace".to_string()),
+        }
+    }
+
+    pub fn invalid_batch(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "InvalidBatch".to_string(),
+            message,
+            details: None,
+            field: Some("batch".to_string()),
+        }
+    }
+
+    pub fn invalid_delivery(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "InvalidDelivery".to_string(),
+            message,
+            details: None,
+            field: Some("delivery".to_string()),
+        }
+    }
+
+    pub fn invalid_resample(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "InvalidResample".to_string(),
+            message,
+            details: None,
+            field: Some("resample".to_string()),
+        }
+    }
+
+    pub fn invalid_indicators(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "InvalidIndicators".to_string(),
+            message,
+            details: None,
+            field: Some("indicators".to_string()),
+        }
+    }
+
+    pub fn not_acceptable(message: String) -> Self {
+        Self {
+            status: StatusCode::NOT_ACCEPTABLE,
+            error_type: "NotAcceptable".to_string(),
+            message,
+            details: None,
+            field: None,
+        }
+    }
+
+    pub fn fetch_failed(message: String, details: Option<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_GATEWAY,
+            error_type: "FetchFailed".to_string(),
+            message,
+            details,
+            field: None,
+        }
+    }
+
     pub fn invalid_query(message: String, details: Option<String>) -> Self {
         Self {
             status: StatusCode::BAD_REQUEST,
@@ -0,0 +1,66 @@
+use crate::requests::tk_interval::TickerInterval;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    #[error("Unknown market data provider: {name}")]
+    UnknownProvider { name: String },
+    #[error("No stored history for {ticker} ({interval})")]
+    NoStoredHistory {
+        ticker: String,
+        interval: TickerInterval,
+    },
+    #[error("Upstream call gave up after {attempts} attempt(s)")]
+    RetriesExhausted { attempts: u32 },
+    #[error("Market data provider {provider} is unavailable, next try in {retry_in_secs}s")]
+    CircuitOpen {
+        provider: String,
+        retry_in_secs: u64,
+    },
+    #[error("No source could serve {ticker} (tried {sources})")]
+    AllSourcesFailed {
+        ticker: String,
+        sources: String,
+        // whether any source in the chain was down, not just the last one
+        outage: bool,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum DatasetError {
+    #[error("No dataset file for {ticker} in {dir}")]
+    NotFound { ticker: String, dir: String },
+    #[error("Dataset holds {available} bars, not {requested}")]
+    IntervalMismatch {
+        available: String,
+        requested: String,
+    },
+    #[error("Unknown timezone: {name}")]
+    UnknownTimezone { name: String },
+    #[error("Missing column `{column}` in {path}")]
+    MissingColumn { column: String, path: String },
+    #[error("{count} malformed row(s) in {path}: {rows}")]
+    MalformedRows {
+        path: String,
+        count: usize,
+        rows: String,
+    },
+}
+
+// The failure of a fetch shared by concurrent callers; its source keeps the typed causes
+#[derive(Debug, Clone)]
+pub struct SharedFetchError(pub Arc<anyhow::Error>);
+
+impl fmt::Display for SharedFetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Shared upstream fetch failed")
+    }
+}
+
+impl std::error::Error for SharedFetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.0.as_ref().as_ref())
+    }
+}
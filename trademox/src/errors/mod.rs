@@ -1,4 +1,5 @@
 pub mod api_error;
+pub mod md_errors;
 pub mod tk_errors;
 pub mod ws_errors;
 pub mod yf_error;
@@ -1,8 +1,20 @@
-use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
-use serde_json::json;
+use crate::websocket::ws_protocol::{FrameBody, FrameTag};
+use crate::websocket::ws_sender::{send_frame_to_socket, OutboundSender};
+use thiserror::Error;
 
-pub async fn send_error_to_socket(socket: &mut WebSocket, e: &anyhow::Error) {
-    let error_msg: Utf8Bytes = json!({"error": e.to_string()}).to_string().into();
-    let msg_text = Message::text(error_msg);
-    let _ = socket.send(msg_text).await;
+#[derive(Debug, Error)]
+pub enum SubscriptionError {
+    #[error("Unknown subscription: {subscription_id}")]
+    UnknownSubscription { subscription_id: String },
+    #[error("Unsupported protocol version {version}, expected {expected}")]
+    UnsupportedVersion { version: u32, expected: u32 },
+    #[error("Subscribe requires at least one symbol")]
+    NoSymbols,
+}
+
+pub async fn send_error_to_socket(out: &OutboundSender, tag: &FrameTag, e: &anyhow::Error) {
+    let body = FrameBody::Error {
+        error: e.to_string(),
+    };
+    let _ = send_frame_to_socket(out, tag, None, body).await;
 }
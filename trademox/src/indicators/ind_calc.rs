@@ -0,0 +1,170 @@
+use crate::creator::tk_creator::TickerParams;
+use crate::indicators::ind_spec::IndicatorSpec;
+use rust_decimal::prelude::ToPrimitive;
+
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    let mut sum = 0.0;
+    for (i, value) in values.iter().enumerate() {
+        sum += value;
+        if i >= period {
+            sum -= values[i - period];
+        }
+        if i + 1 >= period {
+            out[i] = Some(sum / period as f64);
+        }
+    }
+    out
+}
+
+// Seeded with the SMA of the first `period` values
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if values.len() < period {
+        return out;
+    }
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut prev = values[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = Some(prev);
+    for (value, slot) in values.iter().zip(out.iter_mut()).skip(period) {
+        prev += alpha * (value - prev);
+        *slot = Some(prev);
+    }
+    out
+}
+
+// Wilder's smoothing of average gains and losses
+pub fn rsi(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if values.len() <= period {
+        return out;
+    }
+
+    let change = |i: usize| values[i] - values[i - 1];
+    let (mut gain, mut loss) = (1..=period).fold((0.0, 0.0), |(g, l), i| {
+        let c = change(i);
+        (g + c.max(0.0), l + (-c).max(0.0))
+    });
+    gain /= period as f64;
+    loss /= period as f64;
+
+    let value = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+    out[period] = Some(value(gain, loss));
+    for (i, slot) in out.iter_mut().enumerate().skip(period + 1) {
+        let c = change(i);
+        gain = (gain * (period - 1) as f64 + c.max(0.0)) / period as f64;
+        loss = (loss * (period - 1) as f64 + (-c).max(0.0)) / period as f64;
+        *slot = Some(value(gain, loss));
+    }
+    out
+}
+
+// (macd, signal, histogram); the signal line starts once the MACD line has `signal` values
+pub fn macd(
+    values: &[f64],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> Vec<Option<(f64, f64, f64)>> {
+    let fast_ema = ema(values, fast);
+    let slow_ema = ema(values, slow);
+    let line = fast_ema
+        .iter()
+        .zip(&slow_ema)
+        .map(|(f, s)| Some((*f)? - (*s)?))
+        .collect::<Vec<_>>();
+
+    let mut out = vec![None; values.len()];
+    let Some(first) = line.iter().position(Option::is_some) else {
+        return out;
+    };
+    let tail = line[first..].iter().flatten().copied().collect::<Vec<_>>();
+    for (i, signal_value) in ema(&tail, signal).into_iter().enumerate() {
+        if let Some(signal_value) = signal_value {
+            let macd_value = tail[i];
+            out[first + i] = Some((macd_value, signal_value, macd_value - signal_value));
+        }
+    }
+    out
+}
+
+// (upper, middle, lower) bands at `k` population standard deviations
+pub fn bollinger(values: &[f64], period: usize, k: f64) -> Vec<Option<(f64, f64, f64)>> {
+    sma(values, period)
+        .into_iter()
+        .enumerate()
+        .map(|(i, middle)| {
+            let middle = middle?;
+            let window = &values[i + 1 - period..=i];
+            let variance = window.iter().map(|v| (v - middle).powi(2)).sum::<f64>() / period as f64;
+            let width = k * variance.sqrt();
+            Some((middle + width, middle, middle - width))
+        })
+        .collect()
+}
+
+// Adds each indicator on the close to the candle's `indicators` map, skipping warm-up bars
+pub fn apply_indicators(candles: &mut [TickerParams], specs: &[IndicatorSpec]) {
+    let closes = candles
+        .iter()
+        .map(|c| c.close.to_f64().unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    for spec in specs {
+        let label = spec.to_string();
+        let columns: Vec<(String, Vec<Option<f64>>)> = match spec {
+            IndicatorSpec::Sma { period } => vec![(label, sma(&closes, *period))],
+            IndicatorSpec::Ema { period } => vec![(label, ema(&closes, *period))],
+            IndicatorSpec::Rsi { period } => vec![(label, rsi(&closes, *period))],
+            IndicatorSpec::Macd { fast, slow, signal } => {
+                let values = macd(&closes, *fast, *slow, *signal);
+                vec![
+                    (
+                        label.clone(),
+                        values.iter().map(|v| v.map(|t| t.0)).collect(),
+                    ),
+                    (
+                        format!("{label}_signal"),
+                        values.iter().map(|v| v.map(|t| t.1)).collect(),
+                    ),
+                    (
+                        format!("{label}_hist"),
+                        values.iter().map(|v| v.map(|t| t.2)).collect(),
+                    ),
+                ]
+            }
+            IndicatorSpec::Bollinger { period, k } => {
+                let values = bollinger(&closes, *period, *k);
+                vec![
+                    (
+                        format!("{label}_upper"),
+                        values.iter().map(|v| v.map(|t| t.0)).collect(),
+                    ),
+                    (
+                        format!("{label}_middle"),
+                        values.iter().map(|v| v.map(|t| t.1)).collect(),
+                    ),
+                    (
+                        format!("{label}_lower"),
+                        values.iter().map(|v| v.map(|t| t.2)).collect(),
+                    ),
+                ]
+            }
+        };
+
+        for (key, values) in columns {
+            for (candle, value) in candles.iter_mut().zip(values) {
+                if let Some(value) = value {
+                    candle.indicators.insert(key.clone(), value);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// One requested indicator, as `{"name":"ema","period":20}` or `ema:20` in a query string
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum IndicatorSpec {
+    Sma {
+        period: usize,
+    },
+    Ema {
+        period: usize,
+    },
+    Rsi {
+        #[serde(default = "default_rsi_period")]
+        period: usize,
+    },
+    Macd {
+        #[serde(default = "default_macd_fast")]
+        fast: usize,
+        #[serde(default = "default_macd_slow")]
+        slow: usize,
+        #[serde(default = "default_macd_signal")]
+        signal: usize,
+    },
+    Bollinger {
+        #[serde(default = "default_bollinger_period")]
+        period: usize,
+        #[serde(default = "default_bollinger_k")]
+        k: f64,
+    },
+}
+
+fn default_rsi_period() -> usize {
+    14
+}
+
+fn default_macd_fast() -> usize {
+    12
+}
+
+fn default_macd_slow() -> usize {
+    26
+}
+
+fn default_macd_signal() -> usize {
+    9
+}
+
+fn default_bollinger_period() -> usize {
+    20
+}
+
+fn default_bollinger_k() -> f64 {
+    2.0
+}
+
+impl IndicatorSpec {
+    pub fn validate(&self) -> Result<()> {
+        let periods = match self {
+            IndicatorSpec::Sma { period }
+            | IndicatorSpec::Ema { period }
+            | IndicatorSpec::Rsi { period } => vec![*period],
+            IndicatorSpec::Macd { fast, slow, signal } => {
+                if fast >= slow {
+                    return Err(anyhow!("Invalid indicator {self}: fast must be below slow"));
+                }
+                vec![*fast, *slow, *signal]
+            }
+            IndicatorSpec::Bollinger { period, k } => {
+                if !(k.is_finite() && *k > 0.0) {
+                    return Err(anyhow!("Invalid indicator {self}: k must be positive"));
+                }
+                vec![*period]
+            }
+        };
+        if periods.iter().any(|p| !(1..=500).contains(p)) {
+            return Err(anyhow!(
+                "Invalid indicator {self}: periods must be between 1 and 500"
+            ));
+        }
+        Ok(())
+    }
+
+    // Bars needed before the first output so it matches a series with full history
+    pub fn warmup_bars(&self) -> usize {
+        match self {
+            IndicatorSpec::Sma { period } | IndicatorSpec::Bollinger { period, .. } => *period,
+            // exponential smoothing needs a few periods to forget its seed
+            IndicatorSpec::Ema { period } | IndicatorSpec::Rsi { period } => period * 4,
+            IndicatorSpec::Macd { slow, signal, .. } => (slow + signal) * 4,
+        }
+    }
+}
+
+impl fmt::Display for IndicatorSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndicatorSpec::Sma { period } => write!(f, "sma_{period}"),
+            IndicatorSpec::Ema { period } => write!(f, "ema_{period}"),
+            IndicatorSpec::Rsi { period } => write!(f, "rsi_{period}"),
+            IndicatorSpec::Macd { fast, slow, signal } => write!(f, "macd_{fast}_{slow}_{signal}"),
+            IndicatorSpec::Bollinger { period, k } => write!(f, "bollinger_{period}_{k}"),
+        }
+    }
+}
+
+impl FromStr for IndicatorSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(':');
+        let name = parts.next().unwrap_or_default();
+        let args = parts
+            .map(|p| p.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("Invalid indicator: {s}"))?;
+        let arg = |i: usize, default: usize| match args.get(i) {
+            None => Ok(default),
+            Some(a) if a.fract() == 0.0 && *a >= 1.0 => Ok(*a as usize),
+            Some(_) => Err(anyhow!("Invalid indicator: {s}")),
+        };
+
+        let spec = match name {
+            "sma" if args.len() == 1 => IndicatorSpec::Sma { period: arg(0, 0)? },
+            "ema" if args.len() == 1 => IndicatorSpec::Ema { period: arg(0, 0)? },
+            "rsi" if args.len() <= 1 => IndicatorSpec::Rsi {
+                period: arg(0, default_rsi_period())?,
+            },
+            "macd" if args.len() <= 3 => IndicatorSpec::Macd {
+                fast: arg(0, default_macd_fast())?,
+                slow: arg(1, default_macd_slow())?,
+                signal: arg(2, default_macd_signal())?,
+            },
+            "bollinger" if args.len() <= 2 => IndicatorSpec::Bollinger {
+                period: arg(0, default_bollinger_period())?,
+                k: args.get(1).copied().unwrap_or_else(default_bollinger_k),
+            },
+            _ => return Err(anyhow!("Invalid indicator: {s}")),
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IndicatorsInput {
+    Text(String),
+    List(Vec<IndicatorSpec>),
+}
+
+// Accepts the JSON list or a comma separated query value such as `ema:20,rsi:14`
+pub fn deserialize_indicators<'de, D>(deserializer: D) -> Result<Vec<IndicatorSpec>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match IndicatorsInput::deserialize(deserializer)? {
+        IndicatorsInput::List(specs) => Ok(specs),
+        IndicatorsInput::Text(text) => text
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.parse::<IndicatorSpec>())
+            .collect::<Result<_>>()
+            .map_err(serde::de::Error::custom),
+    }
+}
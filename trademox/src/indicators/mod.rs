@@ -0,0 +1,2 @@
+pub mod ind_calc;
+pub mod ind_spec;
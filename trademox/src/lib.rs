@@ -3,9 +3,11 @@ pub mod configs;
 pub mod creator;
 pub mod data;
 pub mod errors;
+pub mod indicators;
 pub mod logging;
 pub mod requests;
+pub mod rest;
 pub mod websocket;
 
-pub use crate::app::app_box::build_app;
+pub use crate::app::app_box::{build_app, build_app_with_provider};
 pub use crate::websocket::ws_router::create_socket_router;
@@ -1,6 +1,3 @@
-use crate::app::app_box::build_app;
-use crate::logging::tracing_initial::init_tracing;
-use crate::websocket::ws_router::create_socket_router;
 use anyhow::Result;
 use dotenv::dotenv;
 use std::env::var;
@@ -8,15 +5,8 @@ use std::error::Error;
 use tokio::net::TcpListener;
 use tokio::signal;
 use tracing::info;
-
-mod app;
-mod configs;
-mod creator;
-mod data;
-mod errors;
-mod logging;
-mod requests;
-pub mod websocket;
+use tradebox::logging::tracing_initial::init_tracing;
+use tradebox::{build_app, create_socket_router};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -33,7 +23,7 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     let listener = TcpListener::bind(&addr).await?;
     info!("Server listening on {}:{}", host, port);
-    let _ = axum::serve(listener, router)
+    axum::serve(listener, router)
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 
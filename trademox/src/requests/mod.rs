@@ -1,3 +1,9 @@
+pub mod tk_asset;
+pub mod tk_batch;
+pub mod tk_delivery;
+pub mod tk_interval;
+pub mod tk_pace;
 pub mod tk_request;
 pub mod tk_request_checker;
 pub mod tk_request_validator;
+pub mod tk_resample;
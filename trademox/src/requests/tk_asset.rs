@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const FIAT: [&str; 22] = [
+    "USD", "EUR", "GBP", "JPY", "CHF", "CAD", "AUD", "NZD", "CNY", "HKD", "SGD", "SEK", "NOK",
+    "DKK", "INR", "KRW", "RUB", "TRY", "BRL", "MXN", "ZAR", "PLN",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Equity,
+    Crypto,
+    Forex,
+    Index,
+    Future,
+}
+
+impl AssetClass {
+    // From Yahoo symbol conventions: `EURUSD=X`, `GC=F`, `^GSPC`, `BTC-USD`; `EUR-USD` is a currency pair too
+    pub fn from_ticker(ticker: &str) -> Self {
+        let ticker = ticker.to_ascii_uppercase();
+        if ticker.ends_with("=X") {
+            return AssetClass::Forex;
+        }
+        if ticker.ends_with("=F") {
+            return AssetClass::Future;
+        }
+        if ticker.starts_with('^') {
+            return AssetClass::Index;
+        }
+        // share classes such as `BRK-B` have a one-letter suffix
+        match ticker.split_once('-') {
+            Some((base, quote)) if FIAT.contains(&quote) && FIAT.contains(&base) => {
+                AssetClass::Forex
+            }
+            Some((base, quote)) if FIAT.contains(&quote) && base.len() > 1 => AssetClass::Crypto,
+            _ => AssetClass::Equity,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetClass::Equity => "equity",
+            AssetClass::Crypto => "crypto",
+            AssetClass::Forex => "forex",
+            AssetClass::Index => "index",
+            AssetClass::Future => "future",
+        }
+    }
+}
+
+impl fmt::Display for AssetClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
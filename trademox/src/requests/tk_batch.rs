@@ -0,0 +1,47 @@
+use anyhow::{anyhow, Error};
+use std::fmt;
+use std::str::FromStr;
+
+// How candles are grouped into frames: one per frame, `count:<n>` or `bytes:<budget>`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TickerBatch {
+    #[default]
+    Single,
+    Count(usize),
+    Bytes(usize),
+}
+
+impl fmt::Display for TickerBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TickerBatch::Single => f.write_str("single"),
+            TickerBatch::Count(count) => write!(f, "count:{count}"),
+            TickerBatch::Bytes(bytes) => write!(f, "bytes:{bytes}"),
+        }
+    }
+}
+
+impl FromStr for TickerBatch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "single" {
+            return Ok(TickerBatch::Single);
+        }
+
+        let (mode, arg) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid batch: {s}"))?;
+        let size = arg
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("Invalid batch, expected a positive size: {s}"))?;
+
+        match mode {
+            "count" => Ok(TickerBatch::Count(size)),
+            "bytes" => Ok(TickerBatch::Bytes(size)),
+            _ => Err(anyhow!("Invalid batch: {s}")),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::{anyhow, Error};
+use std::fmt;
+use std::str::FromStr;
+
+// How a snapshot reaches the client: candle frames or one Arrow IPC binary frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TickerDelivery {
+    #[default]
+    Frames,
+    Arrow,
+}
+
+impl TickerDelivery {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TickerDelivery::Frames => "frames",
+            TickerDelivery::Arrow => "arrow",
+        }
+    }
+}
+
+impl fmt::Display for TickerDelivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TickerDelivery {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "frames" => Ok(TickerDelivery::Frames),
+            "arrow" => Ok(TickerDelivery::Arrow),
+            _ => Err(anyhow!("Invalid delivery: {s}")),
+        }
+    }
+}
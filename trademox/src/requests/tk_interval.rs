@@ -0,0 +1,126 @@
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum TickerInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[default]
+    #[serde(rename = "1d")]
+    OneDay,
+    #[serde(rename = "1wk")]
+    OneWeek,
+    #[serde(rename = "1mo")]
+    OneMonth,
+}
+
+impl TickerInterval {
+    pub const ALL: [TickerInterval; 8] = [
+        TickerInterval::OneMinute,
+        TickerInterval::FiveMinutes,
+        TickerInterval::FifteenMinutes,
+        TickerInterval::ThirtyMinutes,
+        TickerInterval::OneHour,
+        TickerInterval::OneDay,
+        TickerInterval::OneWeek,
+        TickerInterval::OneMonth,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TickerInterval::OneMinute => "1m",
+            TickerInterval::FiveMinutes => "5m",
+            TickerInterval::FifteenMinutes => "15m",
+            TickerInterval::ThirtyMinutes => "30m",
+            TickerInterval::OneHour => "1h",
+            TickerInterval::OneDay => "1d",
+            TickerInterval::OneWeek => "1wk",
+            TickerInterval::OneMonth => "1mo",
+        }
+    }
+
+    pub fn is_intraday(&self) -> bool {
+        self.minutes().is_some()
+    }
+
+    pub fn minutes(&self) -> Option<i64> {
+        match self {
+            TickerInterval::OneMinute => Some(1),
+            TickerInterval::FiveMinutes => Some(5),
+            TickerInterval::FifteenMinutes => Some(15),
+            TickerInterval::ThirtyMinutes => Some(30),
+            TickerInterval::OneHour => Some(60),
+            _ => None,
+        }
+    }
+
+    // Widest date span a single upstream request may cover for this interval
+    pub fn chunk_days(&self) -> i64 {
+        match self {
+            TickerInterval::OneMinute => 7,
+            TickerInterval::FiveMinutes
+            | TickerInterval::FifteenMinutes
+            | TickerInterval::ThirtyMinutes => 59,
+            TickerInterval::OneHour => 729,
+            _ => 365,
+        }
+    }
+
+    // Calendar days holding at least `bars` bars, with room for weekends and holidays
+    pub fn days_for_bars(&self, bars: usize) -> i64 {
+        let bars = bars as i64;
+        match self.minutes() {
+            // a regular session has 390 minutes
+            Some(minutes) => {
+                let per_day = (390 / minutes).max(1);
+                (bars + per_day - 1) / per_day * 7 / 5 + 3
+            }
+            None => match self {
+                TickerInterval::OneWeek => bars * 7 + 7,
+                TickerInterval::OneMonth => bars * 31 + 31,
+                _ => bars * 7 / 5 + 10,
+            },
+        }
+    }
+
+    // How far back from today intraday bars are kept upstream
+    pub fn lookback_days(&self) -> Option<i64> {
+        match self {
+            TickerInterval::OneMinute => Some(29),
+            TickerInterval::FiveMinutes
+            | TickerInterval::FifteenMinutes
+            | TickerInterval::ThirtyMinutes => Some(59),
+            TickerInterval::OneHour => Some(729),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TickerInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TickerInterval {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TickerInterval::ALL
+            .into_iter()
+            .find(|i| i.as_str() == s)
+            .ok_or_else(|| anyhow!("Invalid interval: {s}"))
+    }
+}
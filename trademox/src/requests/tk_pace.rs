@@ -0,0 +1,62 @@
+use anyhow::{anyhow, Error};
+use std::fmt;
+use std::str::FromStr;
+
+// How fast a snapshot is streamed: `burst[:batch]`, `rate:<candles/s>` or `replay:<speed>`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TickerPace {
+    Burst { batch: Option<usize> },
+    Rate { per_sec: f64 },
+    Replay { speed: f64 },
+}
+
+impl Default for TickerPace {
+    fn default() -> Self {
+        TickerPace::Rate { per_sec: 10.0 }
+    }
+}
+
+impl fmt::Display for TickerPace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TickerPace::Burst { batch: None } => f.write_str("burst"),
+            TickerPace::Burst { batch: Some(batch) } => write!(f, "burst:{batch}"),
+            TickerPace::Rate { per_sec } => write!(f, "rate:{per_sec}"),
+            TickerPace::Replay { speed } => write!(f, "replay:{speed}"),
+        }
+    }
+}
+
+impl FromStr for TickerPace {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mode, arg) = match s.split_once(':') {
+            Some((mode, arg)) => (mode, Some(arg)),
+            None => (s, None),
+        };
+
+        let positive = |arg: Option<&str>| {
+            arg.and_then(|a| a.parse::<f64>().ok())
+                .filter(|v| v.is_finite() && *v > 0.0)
+                .ok_or_else(|| anyhow!("Invalid pace, expected a positive number: {s}"))
+        };
+
+        match mode {
+            "burst" => {
+                let batch = arg
+                    .map(|a| a.parse::<usize>().ok().filter(|b| *b > 0))
+                    .map(|b| b.ok_or_else(|| anyhow!("Invalid pace, bad burst batch: {s}")))
+                    .transpose()?;
+                Ok(TickerPace::Burst { batch })
+            }
+            "rate" => Ok(TickerPace::Rate {
+                per_sec: positive(arg)?,
+            }),
+            "replay" => Ok(TickerPace::Replay {
+                speed: positive(arg)?,
+            }),
+            _ => Err(anyhow!("Invalid pace: {s}")),
+        }
+    }
+}
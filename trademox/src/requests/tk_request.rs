@@ -1,6 +1,6 @@
-use crate::creator::tk_creator::TickerParams;
+use crate::indicators::ind_spec::{deserialize_indicators, IndicatorSpec};
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::to_string;
 use std::env;
 
@@ -12,21 +12,39 @@ pub struct TickerRequest {
     pub start: String,
     #[serde(default)]
     pub end: String,
+    #[serde(default)]
+    pub interval: String,
+    #[serde(default)]
+    pub pace: String,
+    #[serde(default)]
+    pub batch: String,
+    #[serde(default)]
+    pub delivery: String,
+    #[serde(default)]
+    pub resample: String,
+    #[serde(default)]
+    pub live: bool,
+    #[serde(default, deserialize_with = "deserialize_indicators")]
+    pub indicators: Vec<IndicatorSpec>,
 }
 
 impl TickerRequest {
     pub fn new() -> Self {
         Self {
-            ticker: env::var("DEFAULT_TICKER".to_string()).unwrap_or_else(|_| "XAUUSD".to_string()),
-            start: env::var("DEFAULT_START".to_string())
-                .unwrap_or_else(|_| "2025-01-01".to_string()),
-            end: env::var("DEFAULT_END".to_string()).unwrap_or_else(|_| "2025-02-01".to_string()),
+            ticker: env::var("DEFAULT_TICKER").unwrap_or_else(|_| "XAUUSD".to_string()),
+            start: env::var("DEFAULT_START").unwrap_or_else(|_| "2025-01-01".to_string()),
+            end: env::var("DEFAULT_END").unwrap_or_else(|_| "2025-02-01".to_string()),
+            interval: env::var("DEFAULT_INTERVAL").unwrap_or_else(|_| "1d".to_string()),
+            pace: env::var("DEFAULT_PACE").unwrap_or_default(),
+            batch: env::var("DEFAULT_BATCH").unwrap_or_default(),
+            delivery: env::var("DEFAULT_DELIVERY").unwrap_or_default(),
+            resample: env::var("DEFAULT_RESAMPLE").unwrap_or_default(),
+            live: false,
+            indicators: Vec::new(),
         }
     }
 }
 
-pub fn convert_to_string(tk_params: &TickerParams) -> Result<String> {
-    to_string(tk_params)
-        .context("JSON -> String serialization failed")
-        .map_err(Into::into)
+pub fn convert_to_string<T: Serialize>(tk_params: &T) -> Result<String> {
+    to_string(tk_params).context("JSON -> String serialization failed")
 }
@@ -1,5 +1,9 @@
+use crate::indicators::ind_spec::IndicatorSpec;
 use crate::requests::tk_request::TickerRequest;
-use crate::requests::tk_request_validator::ensure_date_fmt;
+use crate::requests::tk_request_validator::{
+    ensure_batch_fmt, ensure_date_fmt, ensure_delivery_fmt, ensure_interval_fmt, ensure_pace_fmt,
+    ensure_resample_fmt, validate_indicators, validate_live,
+};
 use anyhow::Result;
 use std::env::var;
 
@@ -35,3 +39,57 @@ pub fn get_default_or_end(t_request: &TickerRequest) -> Result<String> {
     };
     ensure_date_fmt(&val, "end")
 }
+
+pub fn get_default_or_interval(t_request: &TickerRequest) -> Result<String> {
+    let interval_req = t_request.interval.trim();
+    let default_interval = var("DEFAULT_INTERVAL").unwrap_or_else(|_| "1d".to_string());
+    let val = if interval_req.is_empty() {
+        default_interval
+    } else {
+        interval_req.to_string()
+    };
+    ensure_interval_fmt(&val)
+}
+
+// An empty pace is kept so the server default from `pacing` applies
+pub fn get_default_or_pace(t_request: &TickerRequest) -> Result<String> {
+    let pace_req = t_request.pace.trim();
+    if pace_req.is_empty() {
+        return Ok(String::new());
+    }
+    ensure_pace_fmt(pace_req)
+}
+
+pub fn get_default_or_batch(t_request: &TickerRequest) -> Result<String> {
+    let batch_req = t_request.batch.trim();
+    if batch_req.is_empty() {
+        return Ok(String::new());
+    }
+    ensure_batch_fmt(batch_req)
+}
+
+pub fn get_default_or_delivery(t_request: &TickerRequest) -> Result<String> {
+    let delivery_req = t_request.delivery.trim();
+    if delivery_req.is_empty() {
+        return Ok(String::new());
+    }
+    ensure_delivery_fmt(delivery_req)
+}
+
+pub fn get_default_or_resample(t_request: &TickerRequest) -> Result<String> {
+    let resample_req = t_request.resample.trim();
+    if resample_req.is_empty() {
+        return Ok(String::new());
+    }
+    ensure_resample_fmt(resample_req)
+}
+
+pub fn get_checked_live(t_request: &TickerRequest) -> Result<bool> {
+    validate_live(t_request.live, &get_default_or_end(t_request)?)?;
+    Ok(t_request.live)
+}
+
+pub fn get_checked_indicators(t_request: &TickerRequest) -> Result<Vec<IndicatorSpec>> {
+    validate_indicators(&t_request.indicators)?;
+    Ok(t_request.indicators.clone())
+}
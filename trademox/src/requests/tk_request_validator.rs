@@ -1,5 +1,13 @@
+use crate::errors::api_error::ApiError;
+use crate::indicators::ind_spec::IndicatorSpec;
+use crate::requests::tk_batch::TickerBatch;
+use crate::requests::tk_delivery::TickerDelivery;
+use crate::requests::tk_interval::TickerInterval;
+use crate::requests::tk_pace::TickerPace;
+use crate::requests::tk_request::TickerRequest;
+use crate::requests::tk_resample::TickerResample;
 use anyhow::{anyhow, Context, Error, Result};
-use chrono::NaiveDate;
+use chrono::{Duration, NaiveDate, Utc};
 use tracing::log::info;
 
 pub fn validate_ticker(ticker: &str) -> Result<(), Error> {
@@ -10,7 +18,13 @@ pub fn validate_ticker(ticker: &str) -> Result<(), Error> {
     if ticker.len() > 10 {
         return Err(anyhow!("Ticker too long"));
     }
-    if !ticker.chars().all(|c| c.is_ascii_uppercase() || c == '-') {
+    // Yahoo symbols: `BRK-B`, `BRK.B`, `7203.T`, `EURUSD=X`, `GC=F`, and indices such as `^GSPC`
+    let symbol = ticker.strip_prefix('^').unwrap_or(ticker);
+    if !symbol.chars().any(|c| c.is_ascii_uppercase())
+        || !symbol
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '='))
+    {
         return Err(anyhow!("Invalid ticker format"));
     }
     Ok(())
@@ -23,6 +37,129 @@ pub fn validate_date(date: &str) -> Result<(), Error> {
         .map_err(|_| anyhow!("Invalid date format"))
 }
 
+pub fn validate_interval(interval: &str) -> Result<(), Error> {
+    info!("Validating interval: '{}'", interval);
+    if interval.is_empty() {
+        return Ok(());
+    }
+    interval.parse::<TickerInterval>().map(|_| ())
+}
+
+pub fn ensure_interval_fmt(interval: &str) -> Result<String> {
+    let parsed = interval.parse::<TickerInterval>().with_context(|| {
+        format!("Invalid interval, expected one of 1m, 5m, 15m, 30m, 1h, 1d, 1wk, 1mo: {interval}")
+    })?;
+    Ok(parsed.to_string())
+}
+
+pub fn validate_pace(pace: &str) -> Result<(), Error> {
+    info!("Validating pace: '{}'", pace);
+    if pace.is_empty() {
+        return Ok(());
+    }
+    pace.parse::<TickerPace>().map(|_| ())
+}
+
+pub fn ensure_pace_fmt(pace: &str) -> Result<String> {
+    let parsed = pace.parse::<TickerPace>().with_context(|| {
+        format!("Invalid pace, expected burst, burst:<batch>, rate:<n> or replay:<speed>: {pace}")
+    })?;
+    Ok(parsed.to_string())
+}
+
+pub fn validate_batch(batch: &str) -> Result<(), Error> {
+    info!("Validating batch: '{}'", batch);
+    if batch.is_empty() {
+        return Ok(());
+    }
+    batch.parse::<TickerBatch>().map(|_| ())
+}
+
+pub fn ensure_batch_fmt(batch: &str) -> Result<String> {
+    let parsed = batch.parse::<TickerBatch>().with_context(|| {
+        format!("Invalid batch, expected single, count:<n> or bytes:<budget>: {batch}")
+    })?;
+    Ok(parsed.to_string())
+}
+
+pub fn validate_delivery(delivery: &str) -> Result<(), Error> {
+    info!("Validating delivery: '{}'", delivery);
+    if delivery.is_empty() {
+        return Ok(());
+    }
+    delivery.parse::<TickerDelivery>().map(|_| ())
+}
+
+pub fn ensure_delivery_fmt(delivery: &str) -> Result<String> {
+    let parsed = delivery
+        .parse::<TickerDelivery>()
+        .with_context(|| format!("Invalid delivery, expected frames or arrow: {delivery}"))?;
+    Ok(parsed.to_string())
+}
+
+// An empty interval means the 1d default
+pub fn validate_resample(resample: &str, interval: &str) -> Result<(), Error> {
+    info!("Validating resample: '{}'", resample);
+    if resample.is_empty() {
+        return Ok(());
+    }
+    let interval = if interval.is_empty() {
+        TickerInterval::default()
+    } else {
+        interval.parse::<TickerInterval>()?
+    };
+    resample.parse::<TickerResample>()?.check_source(interval)
+}
+
+pub fn ensure_resample_fmt(resample: &str) -> Result<String> {
+    let parsed = resample.parse::<TickerResample>().with_context(|| {
+        format!("Invalid resample, expected none, <n>min, <n>d, W, M or Q: {resample}")
+    })?;
+    Ok(parsed.to_string())
+}
+
+// Live polling continues from the end of the history, so the range has to reach today
+pub fn validate_live(live: bool, end: &str) -> Result<(), Error> {
+    info!("Validating live: {} until '{}'", live, end);
+    if !live {
+        return Ok(());
+    }
+    let end = NaiveDate::parse_from_str(end, "%Y-%m-%d").context("Invalid end date format")?;
+    // a day of slack for clients west of UTC
+    if end < Utc::now().date_naive() - Duration::days(1) {
+        return Err(anyhow!("Live mode needs an end date of today or later"));
+    }
+    Ok(())
+}
+
+// Validates every query param of a ticker request, as the HTTP error it maps to
+pub fn validate_ticker_query(query: &TickerRequest) -> Result<(), ApiError> {
+    validate_ticker(&query.ticker).map_err(|e| ApiError::invalid_ticker(e.to_string()))?;
+    validate_date(&query.start)
+        .map_err(|e| ApiError::invalid_date(e.to_string(), "start".to_string()))?;
+    validate_date(&query.end)
+        .map_err(|e| ApiError::invalid_date(e.to_string(), "end".to_string()))?;
+    validate_interval(&query.interval).map_err(|e| ApiError::invalid_interval(e.to_string()))?;
+    validate_pace(&query.pace).map_err(|e| ApiError::invalid_pace(e.to_string()))?;
+    validate_batch(&query.batch).map_err(|e| ApiError::invalid_batch(e.to_string()))?;
+    validate_delivery(&query.delivery).map_err(|e| ApiError::invalid_delivery(e.to_string()))?;
+    validate_resample(&query.resample, &query.interval)
+        .map_err(|e| ApiError::invalid_resample(e.to_string()))?;
+    validate_live(query.live, &query.end)
+        .map_err(|e| ApiError::invalid_date(e.to_string(), "end".to_string()))?;
+    validate_indicators(&query.indicators)
+        .map_err(|e| ApiError::invalid_indicators(e.to_string()))?;
+    Ok(())
+}
+
+pub fn validate_indicators(indicators: &[IndicatorSpec]) -> Result<(), Error> {
+    info!("Validating indicators: {:?}", indicators);
+    if indicators.len() > 10 {
+        return Err(anyhow!("Too many indicators, at most 10 per request"));
+    }
+    indicators.iter().try_for_each(IndicatorSpec::validate)
+}
+
 pub fn ensure_date_fmt(df: &str, field: &str) -> Result<String> {
     let err_msg = format!("Invalid {field} date format, expected %Y-%m-%d: {df}");
     NaiveDate::parse_from_str(df, "%Y-%m-%d").with_context(|| err_msg)?;
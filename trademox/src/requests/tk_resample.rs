@@ -0,0 +1,96 @@
+use crate::requests::tk_interval::TickerInterval;
+use anyhow::{anyhow, Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+// Bar size provider bars are aggregated into: `<n>min`, `<n>d`, `W`, `M` or `Q`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TickerResample {
+    #[default]
+    None,
+    Minutes(u32),
+    Days(u32),
+    Week,
+    Month,
+    Quarter,
+}
+
+impl TickerResample {
+    // Intraday bars build N-minute bars; daily bars build the longer ones
+    pub fn check_source(&self, interval: TickerInterval) -> Result<()> {
+        match self {
+            TickerResample::None => Ok(()),
+            TickerResample::Minutes(n) => match interval.minutes() {
+                Some(minutes) if i64::from(*n) % minutes == 0 => Ok(()),
+                Some(_) => Err(anyhow!(
+                    "Invalid resample, {self} is not a multiple of the {interval} interval"
+                )),
+                None => Err(anyhow!(
+                    "Invalid resample, {self} needs an intraday interval"
+                )),
+            },
+            _ if interval == TickerInterval::OneDay => Ok(()),
+            _ => Err(anyhow!("Invalid resample, {self} needs the 1d interval")),
+        }
+    }
+
+    // Calendar days holding at least `bars` resampled bars
+    pub fn days_for_bars(&self, bars: usize, interval: TickerInterval) -> i64 {
+        let n = match self {
+            TickerResample::None => return interval.days_for_bars(bars),
+            TickerResample::Minutes(n) => {
+                let per_bar = i64::from(*n) / interval.minutes().unwrap_or(1);
+                return interval.days_for_bars(bars * per_bar.max(1) as usize);
+            }
+            TickerResample::Days(n) => return i64::from(*n) * bars as i64 + 10,
+            TickerResample::Week => 7,
+            TickerResample::Month => 31,
+            TickerResample::Quarter => 92,
+        };
+        (bars as i64 + 1) * n
+    }
+}
+
+impl fmt::Display for TickerResample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TickerResample::None => f.write_str("none"),
+            TickerResample::Minutes(n) => write!(f, "{n}min"),
+            TickerResample::Days(n) => write!(f, "{n}d"),
+            TickerResample::Week => f.write_str("W"),
+            TickerResample::Month => f.write_str("M"),
+            TickerResample::Quarter => f.write_str("Q"),
+        }
+    }
+}
+
+impl FromStr for TickerResample {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        let count = |n: &str| {
+            n.parse::<u32>()
+                .ok()
+                .filter(|n| (1..=1440).contains(n))
+                .ok_or_else(|| anyhow!("Invalid resample, expected a size from 1 to 1440: {s}"))
+        };
+
+        match lower.as_str() {
+            "none" => Ok(TickerResample::None),
+            "w" | "1wk" => Ok(TickerResample::Week),
+            // `1m` stays a minute, as in `interval`
+            "m" | "1mo" => Ok(TickerResample::Month),
+            "q" | "1q" => Ok(TickerResample::Quarter),
+            _ => {
+                if let Some(n) = lower.strip_suffix("min") {
+                    Ok(TickerResample::Minutes(count(n)?))
+                } else if let Some(n) = lower.strip_suffix('d') {
+                    Ok(TickerResample::Days(count(n)?))
+                } else {
+                    Err(anyhow!("Invalid resample: {s}"))
+                }
+            }
+        }
+    }
+}
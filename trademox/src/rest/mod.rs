@@ -0,0 +1,2 @@
+pub mod rest_history;
+pub mod rest_stream;
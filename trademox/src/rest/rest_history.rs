@@ -0,0 +1,169 @@
+use crate::app::app_box::AppBox;
+use crate::creator::tk_arrow::{candles_to_arrow_ipc, ARROW_STREAM_MIME};
+use crate::creator::tk_creator::TickerParams;
+use crate::data::ticker_data::TickerData;
+use crate::errors::api_error::ApiError;
+use crate::errors::tk_errors::TickerDateRangeError;
+use crate::requests::tk_request::TickerRequest;
+use crate::requests::tk_request_validator::validate_ticker_query;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use std::fmt::Write;
+use tracing::{info, warn};
+
+pub const CSV_MIME: &str = "text/csv";
+pub const STALE_HEADER: &str = "x-data-stale";
+pub const SOURCE_HEADER: &str = "x-data-source";
+pub const GAPS_HEADER: &str = "x-data-gaps";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Json,
+    Csv,
+    Arrow,
+}
+
+impl HistoryFormat {
+    // First supported media type in the order the client lists them; no header means JSON
+    pub fn from_accept(accept: Option<&HeaderValue>) -> Option<Self> {
+        let Some(accept) = accept else {
+            return Some(HistoryFormat::Json);
+        };
+
+        accept.to_str().ok()?.split(',').find_map(|media| {
+            let mut parts = media.split(';').map(str::trim);
+            let media_type = parts.next()?;
+            // `q=0` marks a type the client refuses
+            let refused = parts.any(|p| {
+                p.strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            if refused {
+                return None;
+            }
+            match media_type {
+                "application/json" | "application/*" | "*/*" => Some(HistoryFormat::Json),
+                "text/csv" | "text/*" => Some(HistoryFormat::Csv),
+                ARROW_STREAM_MIME => Some(HistoryFormat::Arrow),
+                _ => None,
+            }
+        })
+    }
+}
+
+pub async fn get_history(
+    tk_query: Result<Query<TickerRequest>, QueryRejection>,
+    headers: HeaderMap,
+    State(app): State<AppBox>,
+) -> Response {
+    let query = match tk_query {
+        Ok(Query(query)) => query,
+        Err(rej) => {
+            return ApiError::invalid_query(
+                "Invalid query parameters format".to_string(),
+                Some(format!("{:?}", rej)),
+            )
+            .into_response()
+        }
+    };
+    if let Err(e) = validate_ticker_query(&query) {
+        return e.into_response();
+    }
+
+    let Some(format) = HistoryFormat::from_accept(headers.get(ACCEPT)) else {
+        return ApiError::not_acceptable(format!(
+            "Supported formats: application/json, {CSV_MIME}, {ARROW_STREAM_MIME}"
+        ))
+        .into_response();
+    };
+
+    let tk_snapshot = match TickerData::new().fetch_data(&query, &app).await {
+        Ok(tk_snapshot) => tk_snapshot,
+        Err(e) => {
+            if let Some(range_err) = e.downcast_ref::<TickerDateRangeError>() {
+                return ApiError::invalid_date(range_err.to_string(), "end".to_string())
+                    .into_response();
+            }
+            warn!(error = %e, ticker = %query.ticker, "History request failed");
+            return ApiError::fetch_failed(
+                "Fetching ticker data failed".to_string(),
+                Some(format!("{e:#}")),
+            )
+            .into_response();
+        }
+    };
+
+    info!(
+        ticker = %query.ticker,
+        candles = tk_snapshot.candles.len(),
+        ?format,
+        "History sent"
+    );
+
+    let mut response = match format {
+        HistoryFormat::Json => Json(tk_snapshot.candles).into_response(),
+        HistoryFormat::Csv => (
+            [(CONTENT_TYPE, CSV_MIME)],
+            candles_to_csv(&tk_snapshot.candles),
+        )
+            .into_response(),
+        HistoryFormat::Arrow => match candles_to_arrow_ipc(&tk_snapshot.candles) {
+            Ok(bytes) => ([(CONTENT_TYPE, ARROW_STREAM_MIME)], bytes).into_response(),
+            Err(e) => ApiError::fetch_failed(
+                "Encoding Arrow snapshot failed".to_string(),
+                Some(format!("{e:#}")),
+            )
+            .into_response(),
+        },
+    };
+    // the body stays a plain list of candles, so staleness, source and gaps travel in headers
+    if tk_snapshot.stale {
+        response
+            .headers_mut()
+            .insert(STALE_HEADER, HeaderValue::from_static("true"));
+    }
+    // inclusive day ranges as `start/end`, comma separated
+    if !tk_snapshot.gaps.is_empty() {
+        let gaps = tk_snapshot
+            .gaps
+            .iter()
+            .map(|(start, end)| format!("{start}/{end}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Ok(gaps) = HeaderValue::from_str(&gaps) {
+            response.headers_mut().insert(GAPS_HEADER, gaps);
+        }
+    }
+    if let Some(source) = tk_snapshot
+        .source
+        .and_then(|s| HeaderValue::from_str(&s).ok())
+    {
+        response.headers_mut().insert(SOURCE_HEADER, source);
+    }
+    response
+}
+
+pub fn candles_to_csv(candles: &[TickerParams]) -> String {
+    let mut csv = String::from("time,open,high,low,close,volume,unadjusted_close\n");
+    for c in candles {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            c.time.to_rfc3339(),
+            c.open,
+            c.high,
+            c.low,
+            c.close,
+            c.volume.map(|v| v.to_string()).unwrap_or_default(),
+            c.unadjusted_close
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+        );
+    }
+    csv
+}
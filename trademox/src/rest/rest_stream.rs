@@ -0,0 +1,139 @@
+use crate::app::app_box::AppBox;
+use crate::errors::api_error::ApiError;
+use crate::requests::tk_delivery::TickerDelivery;
+use crate::requests::tk_request::TickerRequest;
+use crate::requests::tk_request_validator::validate_ticker_query;
+use crate::websocket::ws_codec::WireFormat;
+use crate::websocket::ws_protocol::FrameTag;
+use crate::websocket::ws_sender::{spawn_stream, Outbound, OUTBOUND_CAPACITY};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::ws::Message;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use chrono::DateTime;
+use futures_util::stream::unfold;
+use std::convert::Infallible;
+use tokio::sync::mpsc::channel;
+use tokio::task::AbortHandle;
+use tracing::{error, info};
+
+pub const LAST_EVENT_ID: &str = "last-event-id";
+
+// Stops the stream task once the client disconnects and the response body is dropped
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Same frames as `/ws`, one SSE `message` per frame with the time of its newest bar as event id
+pub async fn get_stream(
+    tk_query: Result<Query<TickerRequest>, QueryRejection>,
+    headers: HeaderMap,
+    State(app): State<AppBox>,
+) -> Response {
+    let query = match tk_query {
+        Ok(Query(query)) => query,
+        Err(rej) => {
+            return ApiError::invalid_query(
+                "Invalid query parameters format".to_string(),
+                Some(format!("{:?}", rej)),
+            )
+            .into_response()
+        }
+    };
+    if let Err(e) = validate_ticker_query(&query) {
+        return e.into_response();
+    }
+    if query.delivery == TickerDelivery::Arrow.as_str() {
+        return ApiError::invalid_delivery("Arrow delivery needs a WebSocket".to_string())
+            .into_response();
+    }
+
+    let resume_after = match headers.get(LAST_EVENT_ID).map(|id| id.to_str()) {
+        None => None,
+        Some(Ok(id)) if id.trim().is_empty() => None,
+        Some(Ok(id)) => match id
+            .trim()
+            .parse::<i64>()
+            .map(DateTime::from_timestamp_millis)
+        {
+            Ok(Some(time)) => Some(time),
+            Ok(None) => {
+                return ApiError::invalid_query(
+                    "Invalid Last-Event-ID, bar time out of range".to_string(),
+                    None,
+                )
+                .into_response()
+            }
+            Err(e) => {
+                return ApiError::invalid_query(
+                    "Invalid Last-Event-ID, expected a bar time in epoch milliseconds".to_string(),
+                    Some(e.to_string()),
+                )
+                .into_response()
+            }
+        },
+        Some(Err(e)) => {
+            return ApiError::invalid_query(
+                "Invalid Last-Event-ID header".to_string(),
+                Some(e.to_string()),
+            )
+            .into_response()
+        }
+    };
+
+    info!(
+        ticker = %query.ticker,
+        start = %query.start,
+        end = %query.end,
+        ?resume_after,
+        "SSE stream started"
+    );
+
+    let (out, rx) = channel(OUTBOUND_CAPACITY);
+    // SSE events carry JSON, so byte batches are measured as JSON
+    let handle = spawn_stream(
+        &out,
+        &app,
+        query,
+        FrameTag::default(),
+        WireFormat::Json,
+        resume_after,
+    );
+    // the channel closes when the stream task finishes, which ends the response
+    drop(out);
+
+    let events = unfold((rx, AbortOnDrop(handle)), |(mut rx, guard)| async move {
+        loop {
+            let (frame, event) = match rx.recv().await? {
+                // data frames come encoded as JSON text by the stream task
+                Outbound::Encoded(frame, Message::Text(text)) => {
+                    (frame, Event::default().data(text.as_str()))
+                }
+                Outbound::Frame(frame) => match Event::default().json_data(&frame) {
+                    Ok(event) => (frame, event),
+                    // a dropped frame would break the sequence, so the stream ends instead
+                    Err(e) => {
+                        error!(error = %e, "Frame serialization failed, closing stream");
+                        return None;
+                    }
+                },
+                _ => continue,
+            };
+            let event = match frame.cursor {
+                Some(cursor) => event.id(cursor.timestamp_millis().to_string()),
+                None => event,
+            };
+            return Some((Ok::<_, Infallible>(event), (rx, guard)));
+        }
+    });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
@@ -1,5 +1,12 @@
+pub mod ws_batcher;
+pub mod ws_codec;
 pub mod ws_handler;
+pub mod ws_hub;
+pub mod ws_live;
 pub mod ws_msg_handler;
+pub mod ws_pacer;
+pub mod ws_protocol;
 pub mod ws_router;
 pub mod ws_sender;
+pub mod ws_session;
 pub mod ws_upgrade;
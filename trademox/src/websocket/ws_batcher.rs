@@ -0,0 +1,73 @@
+use crate::creator::tk_creator::TickerParams;
+use crate::requests::tk_batch::TickerBatch;
+use crate::websocket::ws_codec::WireFormat;
+use crate::websocket::ws_protocol::FrameBody;
+use anyhow::{Context, Result};
+use std::mem::take;
+
+// Collects candles into frames of the requested size; `Single` keeps one candle per frame
+#[derive(Debug)]
+pub struct CandleBatcher {
+    batch: TickerBatch,
+    format: WireFormat,
+    pending: Vec<TickerParams>,
+    pending_bytes: usize,
+}
+
+impl CandleBatcher {
+    pub fn new(batch: &str, format: WireFormat) -> Result<Self> {
+        let batch = if batch.is_empty() {
+            TickerBatch::default()
+        } else {
+            batch.parse::<TickerBatch>().context("Invalid batch")?
+        };
+        Ok(Self {
+            batch,
+            format,
+            pending: Vec::new(),
+            pending_bytes: 0,
+        })
+    }
+
+    pub fn batch(&self) -> TickerBatch {
+        self.batch
+    }
+
+    // Returns a frame once the pending candles fill the batch
+    pub fn push(&mut self, candle: TickerParams) -> Result<Option<FrameBody>> {
+        match self.batch {
+            TickerBatch::Single => Ok(Some(FrameBody::Candle { candle })),
+            TickerBatch::Count(count) => {
+                self.pending.push(candle);
+                Ok((self.pending.len() >= count).then(|| self.take_frame()))
+            }
+            TickerBatch::Bytes(budget) => {
+                // encoded size of the candle, plus the comma between JSON array items
+                let size = self
+                    .format
+                    .encoded_len(&candle)
+                    .context("Candle serialization failed")?
+                    + usize::from(self.format == WireFormat::Json);
+                let frame = if !self.pending.is_empty() && self.pending_bytes + size > budget {
+                    Some(self.take_frame())
+                } else {
+                    None
+                };
+                self.pending.push(candle);
+                self.pending_bytes += size;
+                Ok(frame)
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> Option<FrameBody> {
+        (!self.pending.is_empty()).then(|| self.take_frame())
+    }
+
+    fn take_frame(&mut self) -> FrameBody {
+        self.pending_bytes = 0;
+        FrameBody::Candles {
+            candles: take(&mut self.pending),
+        }
+    }
+}
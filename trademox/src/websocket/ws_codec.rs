@@ -0,0 +1,62 @@
+use crate::requests::tk_request::convert_to_string;
+use crate::websocket::ws_protocol::ServerFrame;
+use anyhow::{Context, Result};
+use axum::extract::ws::Message;
+use axum::http::HeaderValue;
+use serde::Serialize;
+
+// In server preference order: a client offering several gets the most compact one
+pub const SUBPROTOCOLS: [&str; 3] = ["trademox.msgpack", "trademox.cbor", "trademox.json"];
+
+// Frame encoding picked once per session from the negotiated `Sec-WebSocket-Protocol`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl WireFormat {
+    // No or unknown subprotocol keeps the JSON text frames
+    pub fn from_subprotocol(protocol: Option<&HeaderValue>) -> Self {
+        match protocol.and_then(|p| p.to_str().ok()) {
+            Some("trademox.msgpack") => WireFormat::MsgPack,
+            Some("trademox.cbor") => WireFormat::Cbor,
+            _ => WireFormat::Json,
+        }
+    }
+
+    // Encoded size of a value in this format, for byte-budgeted batches
+    pub fn encoded_len<T: Serialize>(&self, value: &T) -> Result<usize> {
+        match self {
+            WireFormat::Json => Ok(serde_json::to_vec(value)
+                .context("JSON serialization failed")?
+                .len()),
+            WireFormat::MsgPack => Ok(rmp_serde::to_vec_named(value)
+                .context("MessagePack serialization failed")?
+                .len()),
+            WireFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).context("CBOR serialization failed")?;
+                Ok(bytes.len())
+            }
+        }
+    }
+
+    pub fn encode(&self, frame: &ServerFrame) -> Result<Message> {
+        match self {
+            WireFormat::Json => Ok(Message::Text(convert_to_string(frame)?.into())),
+            WireFormat::MsgPack => {
+                let bytes =
+                    rmp_serde::to_vec_named(frame).context("MessagePack serialization failed")?;
+                Ok(Message::Binary(bytes.into()))
+            }
+            WireFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(frame, &mut bytes).context("CBOR serialization failed")?;
+                Ok(Message::Binary(bytes.into()))
+            }
+        }
+    }
+}
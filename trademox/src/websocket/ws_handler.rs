@@ -1,65 +1,96 @@
 use crate::app::app_box::AppBox;
-use crate::errors::ws_errors::send_error_to_socket;
 use crate::requests::tk_request::TickerRequest;
 use crate::requests::tk_request_checker::{
-    get_default_or_end, get_default_or_start, get_default_or_ticker,
+    get_checked_indicators, get_checked_live, get_default_or_batch, get_default_or_delivery,
+    get_default_or_end, get_default_or_interval, get_default_or_pace, get_default_or_resample,
+    get_default_or_start, get_default_or_ticker,
 };
+use crate::websocket::ws_codec::WireFormat;
 use crate::websocket::ws_msg_handler::handle_socket_message;
-use crate::websocket::ws_sender::send_data_to_socket;
+use crate::websocket::ws_protocol::FrameTag;
+use crate::websocket::ws_sender::{
+    spawn_socket_writer, spawn_stream, Outbound, OutboundSender, OUTBOUND_CAPACITY,
+};
+use crate::websocket::ws_session::WsSession;
 use anyhow::Result;
 use axum::extract::ws::{Message, WebSocket};
 use axum::Error;
+use futures_util::StreamExt;
 use std::time::Duration;
-use tokio::time::interval;
+use tokio::sync::mpsc::channel;
+use tokio::time::{interval_at, Instant};
 use tracing::{error, info};
 
-pub async fn handle_socket_data(mut socket: WebSocket, app: AppBox, ticker_req: TickerRequest) {
-    info!("New websocket connection");
+pub async fn handle_socket_data(
+    socket: WebSocket,
+    app: AppBox,
+    ticker_req: TickerRequest,
+    format: WireFormat,
+) {
+    info!(?format, "New websocket connection");
+
+    let (sink, mut stream) = socket.split();
+    let (out, rx) = channel(OUTBOUND_CAPACITY);
+    let writer = spawn_socket_writer(sink, rx, format);
 
     let ticker_req_init = init_ticker_request(&ticker_req)
         .await
         .unwrap_or_else(|_| TickerRequest::new());
 
-    if let Err(e) = send_data_to_socket(&mut socket, &app, &ticker_req_init).await {
-        send_error_to_socket(&mut socket, &e).await;
-        error!(error = %e, "Initial ticker request send failed");
-    }
+    let mut session = WsSession::new(format);
+    let tag = FrameTag::default();
+    let handle = spawn_stream(&out, &app, ticker_req_init, tag.clone(), format, None);
+    session.track_stream(tag, handle);
 
-    let mut ping_interval = interval(Duration::from_secs(30));
+    // the first ping goes out after one period, not while the snapshot is starting
+    let ping_period = Duration::from_secs(30);
+    let mut ping_interval = interval_at(Instant::now() + ping_period, ping_period);
 
     loop {
         tokio::select! {
-                received_msg = socket.recv() => {
-                    if !handle_received_msg(&mut socket, &app, received_msg).await {
-                        break
-                    }
+            received_msg = stream.next() => {
+                if !handle_received_msg(&out, &app, &mut session, received_msg).await {
+                    break
+                }
+            }
+            _ = ping_interval.tick() => {
+                if out.send(Outbound::Message(Message::Ping(vec![].into()))).await.is_err() {
+                    break
+                }
             }
-              _ = ping_interval.tick() => {
-                let _ = socket.send(Message::Ping(vec![].into())).await;
-              }
         }
-
-        info!("WebSocket session ended");
     }
 
+    drop(session);
+    writer.abort();
+    info!("WebSocket session ended");
+
     async fn init_ticker_request(ticker_req: &TickerRequest) -> Result<TickerRequest> {
         Ok(TickerRequest {
             ticker: get_default_or_ticker(ticker_req)?,
             start: get_default_or_start(ticker_req)?,
             end: get_default_or_end(ticker_req)?,
+            interval: get_default_or_interval(ticker_req)?,
+            pace: get_default_or_pace(ticker_req)?,
+            batch: get_default_or_batch(ticker_req)?,
+            delivery: get_default_or_delivery(ticker_req)?,
+            resample: get_default_or_resample(ticker_req)?,
+            live: get_checked_live(ticker_req)?,
+            indicators: get_checked_indicators(ticker_req)?,
         })
     }
 
     async fn handle_received_msg(
-        socket: &mut WebSocket,
+        out: &OutboundSender,
         app: &AppBox,
+        session: &mut WsSession,
         received_msg: Option<Result<Message, Error>>,
     ) -> bool {
         let Some(Ok(msg)) = received_msg else {
             return false;
         };
 
-        match handle_socket_message(socket, app, Ok(msg)).await {
+        match handle_socket_message(out, app, session, Ok(msg)).await {
             Ok(()) => true,
             Err(e) => {
                 error!(error = %e, "Message handling failed");
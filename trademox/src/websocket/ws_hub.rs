@@ -0,0 +1,241 @@
+use crate::app::app_box::AppBox;
+use crate::creator::tk_creator::{TickerParams, TickerSnapshot};
+use crate::data::ticker_data::{warmup_bars, TickerData};
+use crate::requests::tk_request::TickerRequest;
+use crate::websocket::ws_live::LiveTracker;
+use crate::websocket::ws_protocol::FrameBody;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::Duration;
+use tokio::spawn;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+use tokio::sync::OnceCell;
+use tokio::task::AbortHandle;
+use tokio::time::{self, Instant, MissedTickBehavior};
+use tracing::{info, warn};
+
+// Request params that change the data; pace, batch and delivery only change how it is sent
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FeedKey {
+    ticker: String,
+    start: String,
+    end: String,
+    interval: String,
+    resample: String,
+    indicators: Vec<String>,
+    live: bool,
+}
+
+impl From<&TickerRequest> for FeedKey {
+    fn from(tk_request: &TickerRequest) -> Self {
+        Self {
+            ticker: tk_request.ticker.clone(),
+            start: tk_request.start.clone(),
+            end: tk_request.end.clone(),
+            interval: tk_request.interval.clone(),
+            resample: tk_request.resample.clone(),
+            indicators: tk_request
+                .indicators
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            live: tk_request.live,
+        }
+    }
+}
+
+// Shares one upstream fetch and one live poll between every stream with the same `FeedKey`
+#[derive(Debug)]
+pub struct FeedHub {
+    buffer: usize,
+    // how long a history-only snapshot is shared; live feeds stay current through their poll
+    snapshot_ttl: Duration,
+    feeds: Mutex<HashMap<FeedKey, Weak<Feed>>>,
+}
+
+impl FeedHub {
+    pub fn new(buffer: usize, snapshot_ttl: Duration) -> Self {
+        Self {
+            buffer: buffer.max(1),
+            snapshot_ttl,
+            feeds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // The feed lives while a stream holds it; the last one to drop stops its live poll.
+    // A history-only feed older than `snapshot_ttl` is left to its streams and replaced for new ones
+    pub fn join(&self, tk_request: &TickerRequest) -> Arc<Feed> {
+        let key = FeedKey::from(tk_request);
+        let mut feeds = self.feeds.lock().unwrap();
+        feeds.retain(|_, feed| feed.strong_count() > 0);
+        if let Some(feed) = feeds.get(&key).and_then(Weak::upgrade) {
+            if tk_request.live || !feed.expired(self.snapshot_ttl) {
+                return feed;
+            }
+        }
+
+        let feed = Arc::new(Feed {
+            tk_request: tk_request.clone(),
+            shared: Arc::new(FeedShared {
+                snapshot: OnceCell::new(),
+                fetched_at: OnceLock::new(),
+                updates: broadcast::channel(self.buffer).0,
+            }),
+            poller: Mutex::new(None),
+        });
+        feeds.insert(key, Arc::downgrade(&feed));
+        feed
+    }
+
+    pub fn feed_count(&self) -> usize {
+        let feeds = self.feeds.lock().unwrap();
+        feeds
+            .values()
+            .filter(|feed| feed.strong_count() > 0)
+            .count()
+    }
+}
+
+#[derive(Debug)]
+pub struct Feed {
+    tk_request: TickerRequest,
+    shared: Arc<FeedShared>,
+    poller: Mutex<Option<AbortHandle>>,
+}
+
+#[derive(Debug)]
+struct FeedShared {
+    // history plus every live bar seen so far, so late joiners start from the current state
+    snapshot: OnceCell<Mutex<TickerSnapshot>>,
+    fetched_at: OnceLock<Instant>,
+    updates: Sender<FrameBody>,
+}
+
+impl Feed {
+    // Current snapshot and, for live feeds, a receiver for the updates that follow it
+    pub async fn subscribe(
+        &self,
+        app: &AppBox,
+    ) -> Result<(TickerSnapshot, Option<Receiver<FrameBody>>)> {
+        let snapshot = self
+            .shared
+            .snapshot
+            .get_or_try_init(|| async {
+                let tk_snapshot = TickerData::new()
+                    .fetch_data(&self.tk_request, app)
+                    .await
+                    .context("Fetching ticker data failed")?;
+                if self.tk_request.live {
+                    self.start_poller(app, tk_snapshot.candles.last().cloned());
+                }
+                let _ = self.shared.fetched_at.set(Instant::now());
+                Ok::<_, anyhow::Error>(Mutex::new(tk_snapshot))
+            })
+            .await?;
+
+        let snapshot = snapshot.lock().unwrap();
+        let updates = self
+            .tk_request
+            .live
+            .then(|| self.shared.updates.subscribe());
+        Ok((snapshot.clone(), updates))
+    }
+
+    fn expired(&self, ttl: Duration) -> bool {
+        self.shared
+            .fetched_at
+            .get()
+            .is_some_and(|at| at.elapsed() >= ttl)
+    }
+
+    // Bars after `after` as catch-up frames, plus a fresh receiver for a consumer that lagged
+    pub fn resync(&self, after: Option<DateTime<Utc>>) -> (Vec<FrameBody>, Receiver<FrameBody>) {
+        let Some(snapshot) = self.shared.snapshot.get() else {
+            return (Vec::new(), self.shared.updates.subscribe());
+        };
+        let snapshot = snapshot.lock().unwrap();
+        let mut missed = snapshot
+            .candles
+            .iter()
+            .filter(|c| after.is_none_or(|t| c.time >= t))
+            .cloned()
+            .map(|candle| FrameBody::BarClosed { candle })
+            .collect::<Vec<_>>();
+        if let Some(FrameBody::BarClosed { candle }) = missed.pop() {
+            missed.push(FrameBody::BarUpdate { candle });
+        }
+        (missed, self.shared.updates.subscribe())
+    }
+
+    fn start_poller(&self, app: &AppBox, last: Option<TickerParams>) {
+        let end = NaiveDate::parse_from_str(&self.tk_request.end, "%Y-%m-%d")
+            .unwrap_or_else(|_| Utc::now().date_naive());
+        let period = Duration::from_millis(app.live.poll_interval_ms.max(1));
+        let shared = self.shared.clone();
+        let app = app.clone();
+        let tk_request = self.tk_request.clone();
+
+        let handle = spawn(async move {
+            let mut polls = time::interval_at(Instant::now() + period, period);
+            polls.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut tracker = LiveTracker::new(last);
+            let ticker = TickerData::new();
+            // the forming bar is fetched again, so one more than the warm-up
+            let tail = warmup_bars(&tk_request.indicators) + 1;
+            info!(tk_request.ticker = %tk_request.ticker, ?period, "Live polling started");
+
+            loop {
+                polls.tick().await;
+                let since = tracker.since().unwrap_or(end);
+                let Some(snapshot) = shared.snapshot.get() else {
+                    continue;
+                };
+                let history = {
+                    let snapshot = snapshot.lock().unwrap();
+                    let candles = &snapshot.candles;
+                    candles[candles.len().saturating_sub(tail)..].to_vec()
+                };
+                let bars = match ticker
+                    .fetch_latest(&tk_request, &app, since, &history)
+                    .await
+                {
+                    Ok(bars) => bars,
+                    // a failed poll is retried on the next tick
+                    Err(e) => {
+                        warn!(error = %e, tk_request.ticker = %tk_request.ticker, "Live poll failed");
+                        continue;
+                    }
+                };
+                for body in tracker.update(bars) {
+                    // applied and sent under one lock so joiners see each bar exactly once
+                    let mut snapshot = snapshot.lock().unwrap();
+                    apply_bar(&mut snapshot.candles, &body);
+                    let _ = shared.updates.send(body);
+                }
+            }
+        })
+        .abort_handle();
+        *self.poller.lock().unwrap() = Some(handle);
+    }
+}
+
+impl Drop for Feed {
+    fn drop(&mut self) {
+        if let Some(handle) = self.poller.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+}
+
+fn apply_bar(candles: &mut Vec<TickerParams>, body: &FrameBody) {
+    let (FrameBody::BarUpdate { candle } | FrameBody::BarClosed { candle }) = body else {
+        return;
+    };
+    match candles.last_mut() {
+        Some(last) if last.time == candle.time => *last = candle.clone(),
+        Some(last) if last.time > candle.time => {}
+        _ => candles.push(candle.clone()),
+    }
+}
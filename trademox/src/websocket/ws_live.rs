@@ -0,0 +1,45 @@
+use crate::creator::tk_creator::TickerParams;
+use crate::websocket::ws_protocol::FrameBody;
+use chrono::NaiveDate;
+
+// Turns successive polls of the latest bars into `bar_update` and `bar_closed` frames
+#[derive(Debug, Default)]
+pub struct LiveTracker {
+    // last bar sent, still forming until a newer bar shows up
+    forming: Option<TickerParams>,
+}
+
+impl LiveTracker {
+    pub fn new(last: Option<TickerParams>) -> Self {
+        Self { forming: last }
+    }
+
+    // Day the next poll has to start from so the forming bar is fetched again
+    pub fn since(&self) -> Option<NaiveDate> {
+        self.forming.as_ref().map(|c| c.time.date_naive())
+    }
+
+    pub fn update(&mut self, bars: Vec<TickerParams>) -> Vec<FrameBody> {
+        let forming_time = self.forming.as_ref().map(|c| c.time);
+        let mut bars = bars
+            .into_iter()
+            .filter(|c| forming_time.is_none_or(|t| c.time >= t))
+            .peekable();
+
+        let mut frames = Vec::new();
+        while let Some(bar) = bars.next() {
+            // every bar but the newest is closed
+            if bars.peek().is_some() {
+                frames.push(FrameBody::BarClosed { candle: bar });
+                continue;
+            }
+            if self.forming.as_ref() != Some(&bar) {
+                frames.push(FrameBody::BarUpdate {
+                    candle: bar.clone(),
+                });
+            }
+            self.forming = Some(bar);
+        }
+        frames
+    }
+}
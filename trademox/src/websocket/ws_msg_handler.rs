@@ -1,17 +1,37 @@
 use crate::app::app_box::AppBox;
+use crate::errors::ws_errors::send_error_to_socket;
+use crate::errors::ws_errors::SubscriptionError::{NoSymbols, UnsupportedVersion};
 use crate::requests::tk_request::TickerRequest;
 use crate::requests::tk_request_checker::{
-    get_default_or_end, get_default_or_start, get_default_or_ticker,
+    get_checked_indicators, get_checked_live, get_default_or_batch, get_default_or_delivery,
+    get_default_or_end, get_default_or_interval, get_default_or_pace, get_default_or_resample,
+    get_default_or_start, get_default_or_ticker,
 };
-use crate::websocket::ws_sender::send_data_to_socket;
+use crate::requests::tk_request_validator::validate_ticker;
+use crate::websocket::ws_protocol::{
+    ClientEnvelope, ClientMessage, FrameBody, FrameTag, PROTOCOL_VERSION,
+};
+use crate::websocket::ws_sender::{send_frame_to_socket, spawn_stream, Outbound, OutboundSender};
+use crate::websocket::ws_session::WsSession;
 use anyhow::{Context, Result};
-use axum::extract::ws::{Message, WebSocket};
+use axum::extract::ws::Message;
 use axum::Error;
-use serde_json::from_str;
+use serde::Deserialize;
+use serde_json::{from_str, Value};
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+struct LegacyRequest {
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(flatten)]
+    ticker_req: TickerRequest,
+}
 
 pub async fn handle_socket_message(
-    socket: &mut WebSocket,
+    out: &OutboundSender,
     app: &AppBox,
+    session: &mut WsSession,
     msg: Result<Message, Error>,
 ) -> Result<()> {
     let msg = msg?;
@@ -19,21 +39,238 @@ pub async fn handle_socket_message(
     match msg {
         Message::Close(_) => return Ok(()),
         Message::Text(text) => {
-            let ticker_req: TickerRequest = from_str(&text).context("Invalid JSON")?;
-            let ticker = get_default_or_ticker(&ticker_req).context("Invalid ticker")?;
-            let start = get_default_or_start(&ticker_req).context("Invalid start")?;
-            let end = get_default_or_end(&ticker_req).context("Invalid end")?;
-            let ticker_req_init = TickerRequest { ticker, start, end };
-            send_data_to_socket(socket, &app, &ticker_req_init)
-                .await
-                .context("Sending data to socket failed")?;
+            if is_protocol_message(&text) {
+                return handle_protocol_message(out, app, session, &text).await;
+            }
+
+            let legacy_req: LegacyRequest = from_str(&text).context("Invalid JSON")?;
+            let ticker_req_init = check_ticker_request(&legacy_req.ticker_req)?;
+            // a new legacy request preempts whatever legacy stream is still running
+            let cancelled = session.cancel_streams(|t| t.subscription_id.is_none());
+            if cancelled > 0 {
+                info!(cancelled, "Preempted running stream");
+            }
+            let tag = FrameTag::for_request(legacy_req.request_id);
+            let handle = spawn_stream(
+                out,
+                app,
+                ticker_req_init,
+                tag.clone(),
+                session.format(),
+                None,
+            );
+            session.track_stream(tag, handle);
         }
 
         Message::Ping(p) => {
-            socket.send(Message::Pong(p)).await?;
+            out.send(Outbound::Message(Message::Pong(p)))
+                .await
+                .context("Socket writer closed")?;
         }
         Message::Pong(_) => {}
         _ => {}
     }
     Ok(())
 }
+
+// Versioned messages carry an `op` field; anything else is a legacy single-ticker request
+fn is_protocol_message(text: &str) -> bool {
+    from_str::<Value>(text)
+        .map(|v| v.get("op").is_some())
+        .unwrap_or(false)
+}
+
+async fn handle_protocol_message(
+    out: &OutboundSender,
+    app: &AppBox,
+    session: &mut WsSession,
+    text: &str,
+) -> Result<()> {
+    let envelope = match from_str::<ClientEnvelope>(text).context("Invalid protocol message") {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            let request_id = from_str::<Value>(text)
+                .ok()
+                .and_then(|v| v.get("request_id")?.as_str().map(str::to_string));
+            send_error_to_socket(out, &FrameTag::for_request(request_id), &e).await;
+            return Ok(());
+        }
+    };
+
+    let tag = FrameTag::for_request(envelope.request_id.clone());
+    if let Err(e) = dispatch_protocol_message(out, app, session, envelope).await {
+        send_error_to_socket(out, &tag, &e).await;
+    }
+    Ok(())
+}
+
+async fn dispatch_protocol_message(
+    out: &OutboundSender,
+    app: &AppBox,
+    session: &mut WsSession,
+    envelope: ClientEnvelope,
+) -> Result<()> {
+    if envelope.v != PROTOCOL_VERSION {
+        return Err(UnsupportedVersion {
+            version: envelope.v,
+            expected: PROTOCOL_VERSION,
+        }
+        .into());
+    }
+
+    let request_id = envelope.request_id;
+    match envelope.message {
+        ClientMessage::Subscribe {
+            symbols,
+            start,
+            end,
+            interval,
+            pace,
+            batch,
+            delivery,
+            resample,
+            live,
+            indicators,
+            replace,
+        } => {
+            if symbols.is_empty() {
+                return Err(NoSymbols.into());
+            }
+            let tk_requests = symbols
+                .into_iter()
+                .map(|ticker| {
+                    validate_ticker(&ticker)?;
+                    check_ticker_request(&TickerRequest {
+                        ticker,
+                        start: start.clone(),
+                        end: end.clone(),
+                        interval: interval.clone(),
+                        pace: pace.clone(),
+                        batch: batch.clone(),
+                        delivery: delivery.clone(),
+                        resample: resample.clone(),
+                        live,
+                        indicators: indicators.clone(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if let Some(replaced_id) = replace {
+                session.unsubscribe(&replaced_id, &[])?;
+                let cancelled = session
+                    .cancel_streams(|t| t.subscription_id.as_deref() == Some(replaced_id.as_str()));
+                info!(subscription_id = %replaced_id, cancelled, "Subscription replaced");
+            }
+
+            let subscription = session.subscribe(&tk_requests);
+            info!(subscription_id = %subscription.subscription_id, symbols = ?subscription.symbols, "Subscribed");
+            let sub_tag = FrameTag {
+                request_id,
+                subscription_id: Some(subscription.subscription_id.clone()),
+                symbol: None,
+            };
+            send_frame_to_socket(
+                out,
+                &sub_tag,
+                None,
+                FrameBody::Subscribed {
+                    symbols: subscription.symbols.clone(),
+                },
+            )
+            .await?;
+
+            for tk_request in tk_requests {
+                let tag = FrameTag {
+                    symbol: Some(tk_request.ticker.clone()),
+                    ..sub_tag.clone()
+                };
+                let handle =
+                    spawn_stream(out, app, tk_request, tag.clone(), session.format(), None);
+                session.track_stream(tag, handle);
+            }
+        }
+        ClientMessage::Unsubscribe {
+            subscription_id,
+            symbols,
+        } => {
+            let removed = session.unsubscribe(&subscription_id, &symbols)?;
+            session.cancel_streams(|t| {
+                t.subscription_id.as_deref() == Some(subscription_id.as_str())
+                    && t.symbol.as_ref().is_some_and(|s| removed.contains(s))
+            });
+            let tag = FrameTag {
+                request_id,
+                subscription_id: Some(subscription_id),
+                symbol: None,
+            };
+            send_frame_to_socket(
+                out,
+                &tag,
+                None,
+                FrameBody::Unsubscribed { symbols: removed },
+            )
+            .await?;
+        }
+        ClientMessage::List => {
+            send_frame_to_socket(
+                out,
+                &FrameTag::for_request(request_id),
+                None,
+                FrameBody::Subscriptions {
+                    subscriptions: session.list(),
+                },
+            )
+            .await?;
+        }
+        ClientMessage::Ping => {
+            send_frame_to_socket(
+                out,
+                &FrameTag::for_request(request_id),
+                None,
+                FrameBody::Pong,
+            )
+            .await?;
+        }
+        ClientMessage::Cancel {
+            subscription_id,
+            target_request_id,
+        } => {
+            // with no target every running stream on the connection is cancelled
+            let streams = session.cancel_streams(|t| {
+                subscription_id
+                    .as_ref()
+                    .is_none_or(|id| t.subscription_id.as_ref() == Some(id))
+                    && target_request_id
+                        .as_ref()
+                        .is_none_or(|id| t.request_id.as_ref() == Some(id))
+            });
+            if let Some(subscription_id) = &subscription_id {
+                let _ = session.unsubscribe(subscription_id, &[]);
+            }
+            info!(streams, "Streams cancelled");
+            send_frame_to_socket(
+                out,
+                &FrameTag::for_request(request_id),
+                None,
+                FrameBody::Cancelled { streams },
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+fn check_ticker_request(ticker_req: &TickerRequest) -> Result<TickerRequest> {
+    Ok(TickerRequest {
+        ticker: get_default_or_ticker(ticker_req).context("Invalid ticker")?,
+        start: get_default_or_start(ticker_req).context("Invalid start")?,
+        end: get_default_or_end(ticker_req).context("Invalid end")?,
+        interval: get_default_or_interval(ticker_req).context("Invalid interval")?,
+        pace: get_default_or_pace(ticker_req).context("Invalid pace")?,
+        batch: get_default_or_batch(ticker_req).context("Invalid batch")?,
+        delivery: get_default_or_delivery(ticker_req).context("Invalid delivery")?,
+        resample: get_default_or_resample(ticker_req).context("Invalid resample")?,
+        live: get_checked_live(ticker_req).context("Invalid live")?,
+        indicators: get_checked_indicators(ticker_req).context("Invalid indicators")?,
+    })
+}
@@ -0,0 +1,93 @@
+use crate::configs::yaml_config::PacingConfig;
+use crate::requests::tk_pace::TickerPace;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tokio::{task, time};
+
+// Spaces out candle frames according to the requested pace, within the server limits
+#[derive(Debug)]
+pub struct Pacer {
+    pace: TickerPace,
+    min_delay: Duration,
+    max_delay: Duration,
+    prev: Option<DateTime<Utc>>,
+    sent: usize,
+}
+
+impl Pacer {
+    pub fn new(pace: &str, config: &PacingConfig) -> Result<Self> {
+        let pace = if pace.is_empty() {
+            &config.default_pace
+        } else {
+            pace
+        };
+        let pace = pace.parse::<TickerPace>().context("Invalid pace")?;
+
+        let min_delay = if config.max_rate > 0.0 {
+            Duration::from_secs_f64(1.0 / config.max_rate)
+        } else {
+            Duration::ZERO
+        };
+        let pace = match pace {
+            // without a positive cap there is no rate to fall back to
+            TickerPace::Burst { .. } if !config.allow_burst && config.max_rate <= 0.0 => {
+                TickerPace::default()
+            }
+            TickerPace::Burst { .. } if !config.allow_burst => TickerPace::Rate {
+                per_sec: config.max_rate,
+            },
+            TickerPace::Rate { per_sec } if config.max_rate > 0.0 => TickerPace::Rate {
+                per_sec: per_sec.min(config.max_rate),
+            },
+            pace => pace,
+        };
+
+        Ok(Self {
+            pace,
+            min_delay,
+            max_delay: Duration::from_millis(config.max_delay_ms),
+            prev: None,
+            sent: 0,
+        })
+    }
+
+    pub fn pace(&self) -> TickerPace {
+        self.pace
+    }
+
+    // Delay before sending the candle stamped `ts`; the first candle goes out at once
+    pub fn delay(&mut self, ts: DateTime<Utc>) -> Duration {
+        let prev = self.prev.replace(ts);
+        self.sent += 1;
+        let Some(prev) = prev else {
+            return Duration::ZERO;
+        };
+
+        let secs = match self.pace {
+            TickerPace::Burst { .. } => return Duration::ZERO,
+            TickerPace::Rate { per_sec } => 1.0 / per_sec,
+            TickerPace::Replay { speed } => {
+                (ts - prev).to_std().unwrap_or_default().as_secs_f64() / speed
+            }
+        };
+        // a tiny rate or speed overflows a Duration, which is capped like any long gap
+        Duration::try_from_secs_f64(secs)
+            .unwrap_or(Duration::MAX)
+            .clamp(self.min_delay, self.max_delay.max(self.min_delay))
+    }
+
+    pub async fn wait(&mut self, ts: DateTime<Utc>) {
+        let delay = self.delay(ts);
+        if !delay.is_zero() {
+            time::sleep(delay).await;
+            return;
+        }
+        // burst batches let other streams on the socket through in between
+        if let TickerPace::Burst { batch: Some(batch) } = self.pace {
+            if self.sent.is_multiple_of(batch) {
+                task::yield_now().await;
+            }
+        }
+    }
+}
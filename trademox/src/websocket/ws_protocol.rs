@@ -0,0 +1,176 @@
+use crate::creator::tk_creator::{TickerEvent, TickerParams};
+use crate::data::md_ranges::DayRange;
+use crate::indicators::ind_spec::{deserialize_indicators, IndicatorSpec};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientEnvelope {
+    #[serde(default = "default_version")]
+    pub v: u32,
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+fn default_version() -> u32 {
+    PROTOCOL_VERSION
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        symbols: Vec<String>,
+        #[serde(default)]
+        start: String,
+        #[serde(default)]
+        end: String,
+        #[serde(default)]
+        interval: String,
+        #[serde(default)]
+        pace: String,
+        #[serde(default)]
+        batch: String,
+        #[serde(default)]
+        delivery: String,
+        #[serde(default)]
+        resample: String,
+        #[serde(default)]
+        live: bool,
+        #[serde(default, deserialize_with = "deserialize_indicators")]
+        indicators: Vec<IndicatorSpec>,
+        #[serde(default)]
+        replace: Option<String>,
+    },
+    Unsubscribe {
+        subscription_id: String,
+        #[serde(default)]
+        symbols: Vec<String>,
+    },
+    List,
+    Ping,
+    Cancel {
+        #[serde(default)]
+        subscription_id: Option<String>,
+        #[serde(default)]
+        target_request_id: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Subscription {
+    pub subscription_id: String,
+    pub symbols: Vec<String>,
+    pub start: String,
+    pub end: String,
+    pub interval: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub pace: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub batch: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub delivery: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub resample: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub live: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indicators: Vec<IndicatorSpec>,
+}
+
+// Identifies which request, subscription and symbol a frame belongs to
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FrameTag {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+impl FrameTag {
+    pub fn for_request(request_id: Option<String>) -> Self {
+        Self {
+            request_id,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrameBody {
+    SnapshotBegin {
+        candles: usize,
+        events: usize,
+        // served from a cache while the provider is unavailable
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        stale: bool,
+        // data source that served the snapshot
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        // day ranges of the request without bars, e.g. what a stale cache never held
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        gaps: Vec<DayRange>,
+    },
+    Candle {
+        candle: TickerParams,
+    },
+    Candles {
+        candles: Vec<TickerParams>,
+    },
+    Arrow {
+        rows: usize,
+        bytes: usize,
+    },
+    Event {
+        event: TickerEvent,
+    },
+    SnapshotEnd {
+        candles: usize,
+        events: usize,
+    },
+    BarUpdate {
+        candle: TickerParams,
+    },
+    BarClosed {
+        candle: TickerParams,
+    },
+    Lagged {
+        skipped: u64,
+    },
+    Error {
+        error: String,
+    },
+    Pong,
+    Subscribed {
+        symbols: Vec<String>,
+    },
+    Unsubscribed {
+        symbols: Vec<String>,
+    },
+    Subscriptions {
+        subscriptions: Vec<Subscription>,
+    },
+    Cancelled {
+        streams: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerFrame {
+    #[serde(flatten)]
+    pub tag: FrameTag,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    // newest closed bar in the frame, where a resumed stream picks up
+    #[serde(skip)]
+    pub cursor: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub body: FrameBody,
+}
@@ -1,9 +1,15 @@
 use crate::app::app_box::AppBox;
 use crate::configs::yaml_config::YamlConfig;
+use crate::data::md_breaker::CircuitState;
+use crate::rest::rest_history::get_history;
+use crate::rest::rest_stream::get_stream;
 use crate::websocket::ws_upgrade::upgrade_socket_data;
+use axum::extract::State;
 use axum::http::{HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::routing::get;
-use axum::Router;
+use axum::{Json, Router};
+use serde_json::json;
 use std::env;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -27,7 +33,11 @@ pub fn create_socket_router(app: AppBox) -> Router {
 
     Router::new()
         .route("/ws", get(upgrade_socket_data))
+        .route("/v1/history", get(get_history))
+        .route("/v1/stream", get(get_stream))
         .route("/healthz", get(health_check))
+        .route("/cachez", get(cache_stats))
+        .route("/readyz", get(readiness))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(app)
@@ -36,3 +46,26 @@ pub fn create_socket_router(app: AppBox) -> Router {
 async fn health_check() -> StatusCode {
     StatusCode::OK
 }
+
+async fn cache_stats(State(app): State<AppBox>) -> Response {
+    match app.history_cache {
+        Some(history_cache) => Json(history_cache.stats()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+// Not ready while the provider circuit is open; cached data is still served meanwhile
+async fn readiness(State(app): State<AppBox>) -> Response {
+    let provider = app.provider.name().to_string();
+    let Some(breaker) = app.breaker else {
+        return Json(json!({"status": "ready", "provider": provider})).into_response();
+    };
+
+    let health = breaker.health();
+    let (status, label) = match health.state {
+        CircuitState::Open => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+        CircuitState::HalfOpen | CircuitState::Closed => (StatusCode::OK, "ready"),
+    };
+    let body = json!({"status": label, "provider": provider, "circuit": health});
+    (status, Json(body)).into_response()
+}
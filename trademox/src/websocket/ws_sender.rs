@@ -1,33 +1,334 @@
 use crate::app::app_box::AppBox;
-use crate::data::ticker_data::TickerData;
-use crate::requests::tk_request::{convert_to_string, TickerRequest};
+use crate::creator::tk_arrow::candles_to_arrow_ipc;
+use crate::creator::tk_creator::{TickerParams, TickerSnapshot};
+use crate::requests::tk_delivery::TickerDelivery;
+use crate::requests::tk_request::TickerRequest;
+use crate::websocket::ws_batcher::CandleBatcher;
+use crate::websocket::ws_codec::WireFormat;
+use crate::websocket::ws_hub::Feed;
+use crate::websocket::ws_pacer::Pacer;
+use crate::websocket::ws_protocol::{FrameBody, FrameTag, ServerFrame};
 use anyhow::{Context, Result};
 use axum::extract::ws::{Message, WebSocket};
-use std::time::Duration;
-use tokio::time;
-use tracing::info;
+use chrono::{DateTime, Utc};
+use futures_util::stream::SplitSink;
+use futures_util::SinkExt;
+use tokio::spawn;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::{self, Sender};
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{error, info, warn};
+
+pub const OUTBOUND_CAPACITY: usize = 256;
+
+#[derive(Debug)]
+pub enum Outbound {
+    Frame(ServerFrame),
+    // a data frame encoded by its stream, so a frame that cannot be encoded fails the stream
+    Encoded(ServerFrame, Message),
+    Message(Message),
+}
+
+impl Outbound {
+    pub fn into_frame(self) -> Option<ServerFrame> {
+        match self {
+            Outbound::Frame(frame) | Outbound::Encoded(frame, _) => Some(frame),
+            Outbound::Message(_) => None,
+        }
+    }
+}
+
+pub type OutboundSender = Sender<Outbound>;
+
+// Numbers data frames and drops the bars a resuming client already received.
+// Resuming goes by bar time, which stays put while a live feed grows or is fetched again
+struct SeqFrames<'a> {
+    out: &'a OutboundSender,
+    tag: &'a FrameTag,
+    format: WireFormat,
+    seq: u64,
+    resume_after: Option<DateTime<Utc>>,
+    // last snapshot bar of a live feed, which can still change
+    forming: Option<DateTime<Utc>>,
+}
+
+impl SeqFrames<'_> {
+    fn covered(&self, time: DateTime<Utc>) -> bool {
+        self.resume_after.is_some_and(|t| time <= t)
+    }
+
+    async fn send(&mut self, body: FrameBody) -> Result<()> {
+        // an event goes out before the bar of its day, so a covered bar means the event went too
+        let time = match &body {
+            FrameBody::Candle { candle }
+            | FrameBody::BarUpdate { candle }
+            | FrameBody::BarClosed { candle } => Some(candle.time),
+            FrameBody::Candles { candles } => candles.last().map(|c| c.time),
+            FrameBody::Event { event } => Some(event.time()),
+            _ => None,
+        };
+        if time.is_some_and(|t| self.covered(t)) {
+            return Ok(());
+        }
+        if let FrameBody::BarClosed { .. } = &body {
+            self.forming = None;
+        }
+        // a forming bar is no resume point, so a resumed stream gets its latest values
+        let cursor = match &body {
+            FrameBody::Candle { candle } | FrameBody::BarClosed { candle } => Some(candle.time),
+            FrameBody::Candles { candles } => candles
+                .iter()
+                .rev()
+                .map(|c| c.time)
+                .find(|t| Some(*t) != self.forming),
+            _ => None,
+        }
+        .filter(|t| Some(*t) != self.forming);
+
+        self.seq += 1;
+        let frame = ServerFrame {
+            tag: self.tag.clone(),
+            seq: Some(self.seq),
+            cursor,
+            body,
+        };
+        let msg = self.format.encode(&frame)?;
+        self.out
+            .send(Outbound::Encoded(frame, msg))
+            .await
+            .context("Socket writer closed")
+    }
+}
 
 pub async fn send_data_to_socket(
-    socket: &mut WebSocket,
+    out: &OutboundSender,
     app: &AppBox,
     tk_request: &TickerRequest,
+    tag: &FrameTag,
+    format: WireFormat,
+    resume_after: Option<DateTime<Utc>>,
 ) -> Result<()> {
-    let ticker = TickerData::new();
-    let tk_data = ticker
-        .fetch_data(tk_request, app)
-        .await
-        .context("Fetching ticker data failed")?;
+    let mut pacer = Pacer::new(&tk_request.pace, &app.pacing)?;
+    let mut batcher = CandleBatcher::new(&tk_request.batch, format)?;
+    // identical requests share one feed, so only the first one fetches upstream
+    let feed = app.hub.join(tk_request);
+    let (tk_snapshot, updates) = feed.subscribe(app).await?;
 
-    for tk_params in tk_data {
-        let tk_params_str = convert_to_string(&tk_params)?;
-        let _ = socket.send(Message::Text(tk_params_str.into())).await;
-        time::sleep(Duration::from_millis(100)).await;
+    let candles = tk_snapshot.candles.len();
+    let events = tk_snapshot.events.len();
+    let last_candle = tk_snapshot.candles.last().cloned();
+    let stale = tk_snapshot.stale;
+    if stale {
+        warn!(ticker = %tk_request.ticker, "Sending stale snapshot");
     }
+    send_frame_to_socket(
+        out,
+        tag,
+        None,
+        FrameBody::SnapshotBegin {
+            candles,
+            events,
+            stale,
+            source: tk_snapshot.source.clone(),
+            gaps: tk_snapshot.gaps.clone(),
+        },
+    )
+    .await?;
+
+    let mut frames = SeqFrames {
+        out,
+        tag,
+        format,
+        seq: 0,
+        resume_after,
+        forming: last_candle
+            .as_ref()
+            .filter(|_| tk_request.live)
+            .map(|c| c.time),
+    };
+
+    if delivery(tk_request)? == TickerDelivery::Arrow {
+        send_arrow_snapshot(&mut frames, tk_snapshot).await?;
+        send_frame_to_socket(out, tag, None, FrameBody::SnapshotEnd { candles, events }).await?;
+        info!(tk_request.ticker = %tk_request.ticker, "Arrow snapshot sent");
+        return relay_live_updates(&mut frames, &feed, updates, last_candle).await;
+    }
+
+    let mut tk_events = tk_snapshot.events.into_iter().peekable();
+
+    for tk_params in tk_snapshot.candles {
+        // events are sent right before the candle of the same day, so a pending batch goes first
+        if tk_events.peek().is_some_and(|e| e.time() <= tk_params.time) {
+            if let Some(body) = batcher.flush() {
+                frames.send(body).await?;
+            }
+        }
+        while let Some(tk_event) = tk_events.next_if(|e| e.time() <= tk_params.time) {
+            frames.send(FrameBody::Event { event: tk_event }).await?;
+        }
+
+        // bars the client already has are skipped without waiting
+        if frames.covered(tk_params.time) {
+            continue;
+        }
+        pacer.wait(tk_params.time).await;
+        if let Some(body) = batcher.push(tk_params)? {
+            frames.send(body).await?;
+        }
+    }
+
+    if let Some(body) = batcher.flush() {
+        frames.send(body).await?;
+    }
+
+    for tk_event in tk_events {
+        frames.send(FrameBody::Event { event: tk_event }).await?;
+    }
+
+    send_frame_to_socket(out, tag, None, FrameBody::SnapshotEnd { candles, events }).await?;
 
     info!(
         tk_request.ticker = %tk_request.ticker,
+        pace = %pacer.pace(),
+        batch = %batcher.batch(),
+        resume_after = ?frames.resume_after,
         "Data stream sent"
     );
 
-    Ok(())
+    relay_live_updates(&mut frames, &feed, updates, last_candle).await
+}
+
+// Forwards the feed's live bars; a consumer that lagged behind the buffer is caught up from the feed state
+async fn relay_live_updates(
+    frames: &mut SeqFrames<'_>,
+    feed: &Feed,
+    updates: Option<broadcast::Receiver<FrameBody>>,
+    last_candle: Option<TickerParams>,
+) -> Result<()> {
+    let Some(mut updates) = updates else {
+        return Ok(());
+    };
+    let mut last_seen = last_candle.map(|c| c.time);
+
+    loop {
+        match updates.recv().await {
+            Ok(body) => {
+                if let FrameBody::BarUpdate { candle } | FrameBody::BarClosed { candle } = &body {
+                    last_seen = Some(candle.time);
+                }
+                frames.send(body).await?;
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(skipped, "Live consumer lagged, resyncing from feed state");
+                frames.send(FrameBody::Lagged { skipped }).await?;
+                let (missed, fresh) = feed.resync(last_seen);
+                for body in missed {
+                    if let FrameBody::BarUpdate { candle } | FrameBody::BarClosed { candle } = &body
+                    {
+                        last_seen = Some(candle.time);
+                    }
+                    frames.send(body).await?;
+                }
+                updates = fresh;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+fn delivery(tk_request: &TickerRequest) -> Result<TickerDelivery> {
+    if tk_request.delivery.is_empty() {
+        return Ok(TickerDelivery::default());
+    }
+    tk_request.delivery.parse().context("Invalid delivery")
+}
+
+// Events go out as frames first, then an `arrow` header frame and the binary record batch
+async fn send_arrow_snapshot(
+    frames: &mut SeqFrames<'_>,
+    tk_snapshot: TickerSnapshot,
+) -> Result<()> {
+    for tk_event in tk_snapshot.events {
+        frames.send(FrameBody::Event { event: tk_event }).await?;
+    }
+
+    let bytes = candles_to_arrow_ipc(&tk_snapshot.candles)?;
+    let header = FrameBody::Arrow {
+        rows: tk_snapshot.candles.len(),
+        bytes: bytes.len(),
+    };
+    frames.send(header).await?;
+    frames
+        .out
+        .send(Outbound::Message(Message::Binary(bytes.into())))
+        .await
+        .context("Socket writer closed")
+}
+
+pub async fn send_frame_to_socket(
+    out: &OutboundSender,
+    tag: &FrameTag,
+    seq: Option<u64>,
+    body: FrameBody,
+) -> Result<()> {
+    let frame = ServerFrame {
+        tag: tag.clone(),
+        seq,
+        cursor: None,
+        body,
+    };
+    out.send(Outbound::Frame(frame))
+        .await
+        .context("Socket writer closed")
+}
+
+// Streams one request in its own task so the socket loop keeps reading while it runs
+pub fn spawn_stream(
+    out: &OutboundSender,
+    app: &AppBox,
+    tk_request: TickerRequest,
+    tag: FrameTag,
+    format: WireFormat,
+    resume_after: Option<DateTime<Utc>>,
+) -> AbortHandle {
+    let out = out.clone();
+    let app = app.clone();
+    spawn(async move {
+        if let Err(e) =
+            send_data_to_socket(&out, &app, &tk_request, &tag, format, resume_after).await
+        {
+            error!(error = %e, ticker = %tk_request.ticker, "Ticker stream failed");
+            // the whole cause chain, so a client can tell a rate limit from a bad ticker
+            let body = FrameBody::Error {
+                error: format!("{e:#}"),
+            };
+            let _ = send_frame_to_socket(&out, &tag, None, body).await;
+        }
+    })
+    .abort_handle()
+}
+
+pub fn spawn_socket_writer(
+    mut sink: SplitSink<WebSocket, Message>,
+    mut rx: mpsc::Receiver<Outbound>,
+    format: WireFormat,
+) -> JoinHandle<()> {
+    spawn(async move {
+        while let Some(outbound) = rx.recv().await {
+            let msg = match outbound {
+                Outbound::Frame(frame) => match format.encode(&frame) {
+                    Ok(msg) => msg,
+                    // a control frame the client never gets would leave it waiting, so the socket closes
+                    Err(e) => {
+                        error!(error = %e, "Frame serialization failed, closing socket");
+                        break;
+                    }
+                },
+                Outbound::Encoded(_, msg) | Outbound::Message(msg) => msg,
+            };
+            if sink.send(msg).await.is_err() {
+                break;
+            }
+        }
+    })
 }
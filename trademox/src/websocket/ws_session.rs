@@ -0,0 +1,119 @@
+use crate::errors::ws_errors::SubscriptionError::UnknownSubscription;
+use crate::requests::tk_request::TickerRequest;
+use crate::websocket::ws_codec::WireFormat;
+use crate::websocket::ws_protocol::{FrameTag, Subscription};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use tokio::task::AbortHandle;
+
+#[derive(Debug)]
+struct RunningStream {
+    tag: FrameTag,
+    handle: AbortHandle,
+}
+
+// Per-connection subscription registry and the stream tasks started for it
+#[derive(Debug, Default)]
+pub struct WsSession {
+    format: WireFormat,
+    next_id: u64,
+    subscriptions: BTreeMap<String, Subscription>,
+    streams: Vec<RunningStream>,
+}
+
+impl WsSession {
+    pub fn new(format: WireFormat) -> Self {
+        Self {
+            format,
+            next_id: 0,
+            subscriptions: BTreeMap::new(),
+            streams: Vec::new(),
+        }
+    }
+
+    pub fn format(&self) -> WireFormat {
+        self.format
+    }
+
+    pub fn subscribe(&mut self, tk_requests: &[TickerRequest]) -> Subscription {
+        self.next_id += 1;
+        let subscription_id = format!("sub-{}", self.next_id);
+
+        let first = tk_requests.first().cloned().unwrap_or_default();
+        let subscription = Subscription {
+            subscription_id: subscription_id.clone(),
+            symbols: tk_requests.iter().map(|r| r.ticker.clone()).collect(),
+            start: first.start,
+            end: first.end,
+            interval: first.interval,
+            pace: first.pace,
+            batch: first.batch,
+            delivery: first.delivery,
+            resample: first.resample,
+            live: first.live,
+            indicators: first.indicators,
+        };
+        self.subscriptions
+            .insert(subscription_id, subscription.clone());
+        subscription
+    }
+
+    // Drops the given symbols, or the whole subscription when none are given
+    pub fn unsubscribe(
+        &mut self,
+        subscription_id: &str,
+        symbols: &[String],
+    ) -> Result<Vec<String>> {
+        let Some(subscription) = self.subscriptions.get_mut(subscription_id) else {
+            return Err(UnknownSubscription {
+                subscription_id: subscription_id.to_string(),
+            }
+            .into());
+        };
+
+        if symbols.is_empty() {
+            let removed = self.subscriptions.remove(subscription_id).unwrap();
+            return Ok(removed.symbols);
+        }
+
+        let (removed, kept) = subscription
+            .symbols
+            .drain(..)
+            .partition(|s| symbols.contains(s));
+        subscription.symbols = kept;
+        if subscription.symbols.is_empty() {
+            self.subscriptions.remove(subscription_id);
+        }
+        Ok(removed)
+    }
+
+    pub fn list(&self) -> Vec<Subscription> {
+        self.subscriptions.values().cloned().collect()
+    }
+
+    pub fn track_stream(&mut self, tag: FrameTag, handle: AbortHandle) {
+        self.streams.retain(|s| !s.handle.is_finished());
+        self.streams.push(RunningStream { tag, handle });
+    }
+
+    // Aborts every running stream whose tag matches, returning how many were stopped
+    pub fn cancel_streams(&mut self, matches: impl Fn(&FrameTag) -> bool) -> usize {
+        self.streams.retain(|s| !s.handle.is_finished());
+
+        let (cancelled, kept): (Vec<_>, Vec<_>) =
+            self.streams.drain(..).partition(|s| matches(&s.tag));
+        self.streams = kept;
+        for stream in &cancelled {
+            stream.handle.abort();
+        }
+        cancelled.len()
+    }
+}
+
+impl Drop for WsSession {
+    fn drop(&mut self) {
+        for stream in &self.streams {
+            stream.handle.abort();
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::app::app_box::AppBox;
 use crate::errors::api_error::ApiError;
 use crate::requests::tk_request::TickerRequest;
-use crate::requests::tk_request_validator::{validate_date, validate_ticker};
+use crate::requests::tk_request_validator::validate_ticker_query;
+use crate::websocket::ws_codec::{WireFormat, SUBPROTOCOLS};
 use crate::websocket::ws_handler::handle_socket_data;
 use axum::{
     extract::{rejection::QueryRejection, Query, State, WebSocketUpgrade},
@@ -17,14 +18,8 @@ pub async fn upgrade_socket_data(
     match tk_query {
         Ok(query) => {
             //validation of ticker request params
-            if let Err(e) = validate_ticker(&query.0.ticker) {
-                return ApiError::invalid_ticker(e.to_string()).into_response();
-            }
-            if let Err(e) = validate_date(&query.0.start) {
-                return ApiError::invalid_date(e.to_string(), "start".to_string()).into_response();
-            }
-            if let Err(e) = validate_date(&query.0.end) {
-                return ApiError::invalid_date(e.to_string(), "end".to_string()).into_response();
+            if let Err(e) = validate_ticker_query(&query.0) {
+                return e.into_response();
             }
 
             // logging of success response
@@ -32,13 +27,22 @@ pub async fn upgrade_socket_data(
                 ticker = %query.0.ticker,
                 start = %query.0.start,
                 end = %query.0.end,
+                interval = %query.0.interval,
+                pace = %query.0.pace,
+                batch = %query.0.batch,
+                delivery = %query.0.delivery,
+                resample = %query.0.resample,
+                live = query.0.live,
+                indicators = ?query.0.indicators,
                 "WebSocket upgrade successful"
             );
 
-            // WebSocket upgrading
+            // WebSocket upgrading, frames are encoded in the negotiated subprotocol
+            let ws_upgrade = ws_upgrade.protocols(SUBPROTOCOLS);
+            let format = WireFormat::from_subprotocol(ws_upgrade.selected_protocol());
             ws_upgrade.on_upgrade(move |socket| {
                 let span = span!(Level::INFO, "ws_session");
-                handle_socket_data(socket, app, query.0).instrument(span)
+                handle_socket_data(socket, app, query.0, format).instrument(span)
             })
         }
 
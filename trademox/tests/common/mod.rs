@@ -0,0 +1,97 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use axum::{routing::get, serve, Router};
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::spawn;
+use tokio::sync::oneshot::{channel, Sender};
+use tradebox::build_app_with_provider;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::rest::{rest_history, rest_stream};
+use tradebox::websocket::ws_upgrade::upgrade_socket_data;
+
+// One daily candle per requested day, closing at the day of month
+#[derive(Debug, Default)]
+pub struct DayProvider {
+    pub starts: Mutex<Vec<NaiveDate>>,
+    pub down: AtomicBool,
+}
+
+#[async_trait]
+impl MarketDataProvider for DayProvider {
+    fn name(&self) -> &str {
+        "days"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.starts.lock().unwrap().push(start);
+        if self.down.load(Ordering::SeqCst) {
+            return Err(anyhow!("upstream is down"));
+        }
+        Ok(MarketHistory {
+            candles: start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .map(|d| {
+                    let price = Decimal::from(d.day());
+                    MarketCandle {
+                        ts: d.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+                        open: price,
+                        high: price + Decimal::ONE,
+                        low: price - Decimal::ONE,
+                        close: price,
+                        volume: Some(100),
+                        unadjusted_close: None,
+                    }
+                })
+                .collect(),
+            ..MarketHistory::default()
+        })
+    }
+}
+
+// Serves `/ws`, `/v1/history` and `/v1/stream` over the provider until the sender fires
+pub async fn start_test_server(provider: Arc<dyn MarketDataProvider>) -> (String, Sender<()>) {
+    let app = build_app_with_provider(provider);
+    let router = Router::new()
+        .route("/ws", get(upgrade_socket_data))
+        .route("/v1/history", get(rest_history::get_history))
+        .route("/v1/stream", get(rest_stream::get_stream))
+        .with_state(app);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (sender, receiver) = channel::<()>();
+
+    spawn(async move {
+        serve(listener, router)
+            .with_graceful_shutdown(async {
+                receiver.await.ok();
+            })
+            .await
+            .unwrap();
+    });
+
+    (format!("127.0.0.1:{}", addr.port()), sender)
+}
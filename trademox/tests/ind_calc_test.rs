@@ -0,0 +1,90 @@
+use tradebox::indicators::ind_calc::{bollinger, ema, macd, rsi, sma};
+use tradebox::indicators::ind_spec::IndicatorSpec;
+use tradebox::websocket::ws_protocol::ClientMessage;
+
+fn close_to(actual: Option<f64>, expected: f64) -> bool {
+    actual.is_some_and(|a| (a - expected).abs() < 1e-6)
+}
+
+#[test]
+fn sma_and_ema_skip_warmup_bars() {
+    let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+    let sma = sma(&values, 3);
+    assert_eq!(sma[..2], [None, None]);
+    assert_eq!(sma[2..], [Some(2.0), Some(3.0), Some(4.0)]);
+
+    // seeded with the SMA of the first 3 values, alpha = 0.5
+    let ema = ema(&values, 3);
+    assert_eq!(ema[..2], [None, None]);
+    assert_eq!(ema[2..], [Some(2.0), Some(3.0), Some(4.0)]);
+    assert_eq!(self::ema(&[2.0, 4.0, 6.0, 10.0], 3)[3], Some(7.0));
+}
+
+#[test]
+fn rsi_follows_wilder_smoothing() {
+    assert_eq!(
+        rsi(&[1.0, 2.0, 3.0, 4.0], 2)[2..],
+        [Some(100.0), Some(100.0)]
+    );
+
+    // gains 2, losses 1 over the seed, then a loss of 1
+    let values = rsi(&[10.0, 12.0, 11.0, 10.0], 2);
+    assert_eq!(values[..2], [None, None]);
+    assert!(close_to(values[2], 100.0 - 100.0 / 3.0));
+    assert!(close_to(values[3], 40.0));
+}
+
+#[test]
+fn macd_and_bollinger_return_all_lines() {
+    let values = (1..=10).map(f64::from).collect::<Vec<_>>();
+    let lines = macd(&values, 2, 4, 2);
+    // slow EMA starts at index 3, the signal needs 2 MACD values
+    assert!(lines[..4].iter().all(Option::is_none));
+    let (line, signal, hist) = lines[4].unwrap();
+    assert!(close_to(Some(line), 1.0));
+    assert!(close_to(Some(signal), 1.0));
+    assert!(close_to(Some(hist), 0.0));
+
+    let bands = bollinger(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0], 8, 2.0);
+    assert!(bands[..7].iter().all(Option::is_none));
+    assert_eq!(bands[7], Some((9.0, 5.0, 1.0)));
+}
+
+#[test]
+fn indicator_specs_parse_from_query_and_json() {
+    assert_eq!(
+        "ema:20".parse::<IndicatorSpec>().unwrap(),
+        IndicatorSpec::Ema { period: 20 }
+    );
+    assert_eq!(
+        "macd".parse::<IndicatorSpec>().unwrap().to_string(),
+        "macd_12_26_9"
+    );
+    assert_eq!(
+        "bollinger:20:2.5"
+            .parse::<IndicatorSpec>()
+            .unwrap()
+            .to_string(),
+        "bollinger_20_2.5"
+    );
+    for invalid in ["ema", "ema:0", "ema:2.5", "macd:26:12", "wma:3", "sma:501"] {
+        assert!(invalid.parse::<IndicatorSpec>().is_err(), "{invalid}");
+    }
+
+    let msg: ClientMessage = serde_json::from_str(
+        r#"{"op":"subscribe","symbols":["AAPL"],
+            "indicators":[{"name":"ema","period":20},{"name":"rsi"}]}"#,
+    )
+    .unwrap();
+    let ClientMessage::Subscribe { indicators, .. } = msg else {
+        panic!("expected subscribe");
+    };
+    assert_eq!(
+        indicators,
+        vec![
+            IndicatorSpec::Ema { period: 20 },
+            IndicatorSpec::Rsi { period: 14 }
+        ]
+    );
+}
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::channel;
+use tradebox::build_app_with_provider;
+use tradebox::configs::yaml_config::{BreakerConfig, CacheConfig};
+use tradebox::data::md_breaker::{BreakerProvider, CircuitBreaker, CircuitState};
+use tradebox::data::md_cache::CachedProvider;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request::TickerRequest;
+use tradebox::websocket::ws_codec::WireFormat;
+use tradebox::websocket::ws_protocol::FrameTag;
+use tradebox::websocket::ws_sender::{spawn_stream, Outbound};
+
+// One daily bar per requested day, or an error while `down`
+#[derive(Debug, Default)]
+struct FlakyProvider {
+    calls: AtomicUsize,
+    down: AtomicBool,
+}
+
+#[async_trait]
+impl MarketDataProvider for FlakyProvider {
+    fn name(&self) -> &str {
+        "flaky"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.down.load(Ordering::SeqCst) {
+            return Err(anyhow!("upstream is down"));
+        }
+        Ok(MarketHistory {
+            candles: start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .map(|d| MarketCandle {
+                    ts: d.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+                    open: Decimal::TEN,
+                    high: Decimal::TEN,
+                    low: Decimal::TEN,
+                    close: Decimal::TEN,
+                    volume: Some(10),
+                    unadjusted_close: None,
+                })
+                .collect(),
+            ..MarketHistory::default()
+        })
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn guarded(inner: Arc<FlakyProvider>, open_secs: u64) -> (BreakerProvider, Arc<CircuitBreaker>) {
+    let breaker = Arc::new(CircuitBreaker::new(&BreakerConfig {
+        enabled: true,
+        failure_threshold: 2,
+        open_secs,
+    }));
+    (BreakerProvider::new(inner, breaker.clone()), breaker)
+}
+
+#[tokio::test]
+async fn open_circuit_fails_fast_until_a_trial_succeeds() {
+    let inner = Arc::new(FlakyProvider::default());
+    let (provider, breaker) = guarded(inner.clone(), 1);
+    let day = TickerInterval::OneDay;
+    let range = (date(2024, 1, 1), date(2024, 1, 5));
+
+    inner.down.store(true, Ordering::SeqCst);
+    for _ in 0..2 {
+        assert!(provider
+            .fetch_history_data("AAPL", day, range.0, range.1)
+            .await
+            .is_err());
+    }
+    let health = breaker.health();
+    assert_eq!(health.state, CircuitState::Open);
+    assert_eq!(health.consecutive_failures, 2);
+    assert!(health.last_error.unwrap().contains("upstream is down"));
+
+    // no upstream call while open
+    let error = provider
+        .fetch_history_data("AAPL", day, range.0, range.1)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("unavailable"), "{error}");
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+    inner.down.store(false, Ordering::SeqCst);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let history = provider
+        .fetch_history_data("AAPL", day, range.0, range.1)
+        .await
+        .unwrap();
+    assert_eq!(history.candles.len(), 5);
+    assert_eq!(breaker.health().state, CircuitState::Closed);
+    assert_eq!(breaker.health().consecutive_failures, 0);
+}
+
+#[tokio::test]
+async fn cache_serves_expired_bars_as_stale_while_the_upstream_is_down() {
+    let inner = Arc::new(FlakyProvider::default());
+    let cache = Arc::new(CachedProvider::new(
+        inner.clone(),
+        &CacheConfig {
+            enabled: true,
+            ttl_secs: 0,
+            ..CacheConfig::default()
+        },
+    ));
+    let today = Utc::now().date_naive();
+    let start = today - Duration::days(3);
+    let day = TickerInterval::OneDay;
+
+    let fresh = cache
+        .fetch_history_data("AAPL", day, start, today)
+        .await
+        .unwrap();
+    assert!(!fresh.stale);
+
+    inner.down.store(true, Ordering::SeqCst);
+    let stale = cache
+        .fetch_history_data("AAPL", day, start, today)
+        .await
+        .unwrap();
+    assert!(stale.stale);
+    assert_eq!(stale.candles, fresh.candles);
+
+    // nothing cached for another ticker, so its error goes through
+    assert!(cache
+        .fetch_history_data("MSFT", day, start, today)
+        .await
+        .is_err());
+
+    // the snapshot frame tells the client
+    let app = build_app_with_provider(cache);
+    let request = TickerRequest {
+        ticker: "AAPL".into(),
+        start: start.to_string(),
+        end: today.to_string(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: false,
+        indicators: vec![],
+    };
+    let (out, mut rx) = channel(64);
+    spawn_stream(
+        &out,
+        &app,
+        request,
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+    let Some(frame) = rx.recv().await.and_then(Outbound::into_frame) else {
+        panic!("snapshot frame expected");
+    };
+    let frame = serde_json::to_value(&frame).unwrap();
+    assert_eq!(frame["type"], "snapshot_begin");
+    assert_eq!(frame["stale"], Value::Bool(true));
+}
@@ -0,0 +1,406 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use axum::extract::{Query, State};
+use axum::{routing::get, serve, Json, Router};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::spawn;
+use tradebox::configs::yaml_config::{CacheConfig, ProviderConfig};
+use tradebox::data::md_cache::CachedProvider;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::data::yfinance_data::YFinanceData;
+use tradebox::requests::tk_interval::TickerInterval;
+use yfinance_rs::YfClient;
+
+#[derive(Debug, Default)]
+struct RecordingProvider {
+    calls: Mutex<Vec<(NaiveDate, NaiveDate)>>,
+    // answers with this error while set; only "upstream is down" is an outage
+    error: Mutex<Option<&'static str>>,
+    // days without bars, reported back as gaps
+    gap: Mutex<Option<(NaiveDate, NaiveDate)>>,
+    // answers the way a fallback does while the upstream is down
+    stale: AtomicBool,
+}
+
+#[async_trait]
+impl MarketDataProvider for RecordingProvider {
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        e.to_string() == "upstream is down"
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.calls.lock().unwrap().push((start, end));
+        if let Some(error) = *self.error.lock().unwrap() {
+            return Err(anyhow!(error));
+        }
+        let gap = (*self.gap.lock().unwrap())
+            .filter(|(gap_start, gap_end)| *gap_end >= start && *gap_start <= end)
+            .map(|(gap_start, gap_end)| (gap_start.max(start), gap_end.min(end)));
+        let candles = start
+            .iter_days()
+            .take_while(|d| *d <= end)
+            .filter(|d| gap.is_none_or(|(gap_start, gap_end)| !(gap_start..=gap_end).contains(d)))
+            .map(|d| MarketCandle {
+                ts: Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap()),
+                open: Decimal::ONE,
+                high: Decimal::ONE,
+                low: Decimal::ONE,
+                close: Decimal::ONE,
+                volume: Some(1),
+                unadjusted_close: None,
+            })
+            .collect();
+        Ok(MarketHistory {
+            candles,
+            actions: vec![],
+            stale: self.stale.load(Ordering::SeqCst),
+            source: None,
+            gaps: gap.into_iter().collect(),
+        })
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn cached(inner: Arc<RecordingProvider>, ttl_secs: u64) -> CachedProvider {
+    let config = CacheConfig {
+        enabled: true,
+        ttl_secs,
+        ..CacheConfig::default()
+    };
+    CachedProvider::new(inner, &config)
+}
+
+#[tokio::test]
+async fn serves_covered_range_from_cache() {
+    let inner = Arc::new(RecordingProvider::default());
+    let cache = cached(inner.clone(), 300);
+    let day = TickerInterval::OneDay;
+
+    let first = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 10))
+        .await
+        .unwrap();
+    let second = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 3), date(2024, 1, 5))
+        .await
+        .unwrap();
+
+    assert_eq!(first.candles.len(), 10);
+    assert_eq!(second.candles.len(), 3);
+    assert_eq!(inner.calls.lock().unwrap().len(), 1);
+    assert_eq!(cache.stats().hits, 1);
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[tokio::test]
+async fn fetches_only_missing_sub_ranges() {
+    let inner = Arc::new(RecordingProvider::default());
+    let cache = cached(inner.clone(), 300);
+    let day = TickerInterval::OneDay;
+
+    cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 5), date(2024, 1, 10))
+        .await
+        .unwrap();
+    let merged = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 15))
+        .await
+        .unwrap();
+
+    assert_eq!(merged.candles.len(), 15);
+    assert_eq!(
+        *inner.calls.lock().unwrap(),
+        vec![
+            (date(2024, 1, 5), date(2024, 1, 10)),
+            (date(2024, 1, 1), date(2024, 1, 4)),
+            (date(2024, 1, 11), date(2024, 1, 15)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn recent_bars_expire_after_ttl() {
+    let inner = Arc::new(RecordingProvider::default());
+    let cache = cached(inner.clone(), 0);
+    let day = TickerInterval::OneDay;
+    let today = Utc::now().date_naive();
+    let start = today - Duration::days(10);
+
+    cache
+        .fetch_history_data("AAPL", day, start, today)
+        .await
+        .unwrap();
+    cache
+        .fetch_history_data("AAPL", day, start, today)
+        .await
+        .unwrap();
+
+    // only the recent tail is fetched again
+    let calls = inner.calls.lock().unwrap();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1], (today - Duration::days(1), today));
+}
+
+#[tokio::test]
+async fn stale_cache_is_served_on_outages_only_and_names_its_holes() {
+    let inner = Arc::new(RecordingProvider::default());
+    let cache = cached(inner.clone(), 300);
+    let day = TickerInterval::OneDay;
+    cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 5), date(2024, 1, 10))
+        .await
+        .unwrap();
+
+    *inner.error.lock().unwrap() = Some("upstream is down");
+    let stale = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 15))
+        .await
+        .unwrap();
+    assert!(stale.stale);
+    assert_eq!(stale.candles.len(), 6);
+    assert_eq!(
+        stale.gaps,
+        vec![
+            (date(2024, 1, 1), date(2024, 1, 4)),
+            (date(2024, 1, 11), date(2024, 1, 15)),
+        ]
+    );
+
+    // a rejected request is not papered over with cached bars
+    *inner.error.lock().unwrap() = Some("unknown ticker");
+    let error = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 15))
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "unknown ticker");
+
+    // expired bars are stale, but no hole
+    let inner = Arc::new(RecordingProvider::default());
+    let cache = cached(inner.clone(), 0);
+    let today = Utc::now().date_naive();
+    cache
+        .fetch_history_data("AAPL", day, today - Duration::days(3), today)
+        .await
+        .unwrap();
+    *inner.error.lock().unwrap() = Some("upstream is down");
+    let stale = cache
+        .fetch_history_data("AAPL", day, today - Duration::days(5), today)
+        .await
+        .unwrap();
+    assert_eq!(stale.candles.len(), 4);
+    assert_eq!(
+        stale.gaps,
+        vec![(today - Duration::days(5), today - Duration::days(4))]
+    );
+}
+
+#[tokio::test]
+async fn reported_gaps_are_kept_with_the_cached_range() {
+    let inner = Arc::new(RecordingProvider::default());
+    *inner.gap.lock().unwrap() = Some((date(2024, 1, 4), date(2024, 1, 8)));
+    let cache = cached(inner.clone(), 300);
+    let day = TickerInterval::OneDay;
+
+    let first = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 10))
+        .await
+        .unwrap();
+    assert_eq!(first.candles.len(), 5);
+    assert_eq!(first.gaps, [(date(2024, 1, 4), date(2024, 1, 8))]);
+
+    // a read inside the range gets the part of the gap it asked for
+    let inside = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 6), date(2024, 1, 10))
+        .await
+        .unwrap();
+    assert_eq!(inside.candles.len(), 2);
+    assert_eq!(inside.gaps, [(date(2024, 1, 6), date(2024, 1, 8))]);
+
+    // only the gap is asked for again, and once filled upstream it is gone
+    *inner.gap.lock().unwrap() = None;
+    let filled = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 10))
+        .await
+        .unwrap();
+    assert_eq!(filled.candles.len(), 10);
+    assert!(filled.gaps.is_empty());
+    assert_eq!(
+        *inner.calls.lock().unwrap(),
+        [
+            (date(2024, 1, 1), date(2024, 1, 10)),
+            (date(2024, 1, 6), date(2024, 1, 8)),
+            (date(2024, 1, 4), date(2024, 1, 8)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stale_answers_are_passed_on_and_not_cached_as_covered() {
+    let inner = Arc::new(RecordingProvider::default());
+    inner.stale.store(true, Ordering::SeqCst);
+    let cache = cached(inner.clone(), 300);
+    let day = TickerInterval::OneDay;
+
+    let stale = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 5))
+        .await
+        .unwrap();
+    assert!(stale.stale);
+    assert_eq!(stale.candles.len(), 5);
+
+    inner.stale.store(false, Ordering::SeqCst);
+    let fresh = cache
+        .fetch_history_data("AAPL", day, date(2024, 1, 1), date(2024, 1, 5))
+        .await
+        .unwrap();
+    assert!(!fresh.stale);
+    assert_eq!(inner.calls.lock().unwrap().len(), 2);
+
+    // the outermost layer answers for the stack below it
+    assert!(cache.is_outage(&anyhow!("upstream is down")));
+    assert!(!cache.is_outage(&anyhow!("unknown ticker")));
+}
+
+#[tokio::test]
+async fn least_recently_used_entry_is_evicted() {
+    let inner = Arc::new(RecordingProvider::default());
+    let cache = CachedProvider::new(
+        inner.clone(),
+        &CacheConfig {
+            max_entries: 2,
+            ..CacheConfig::default()
+        },
+    );
+    let day = TickerInterval::OneDay;
+    let (start, end) = (date(2024, 1, 1), date(2024, 1, 5));
+
+    for ticker in ["AAPL", "MSFT", "AAPL", "NVDA"] {
+        cache
+            .fetch_history_data(ticker, day, start, end)
+            .await
+            .unwrap();
+    }
+    assert_eq!(cache.len(), 2);
+    assert_eq!(inner.calls.lock().unwrap().len(), 3);
+
+    // AAPL was used after MSFT, so MSFT made room for NVDA
+    cache
+        .fetch_history_data("AAPL", day, start, end)
+        .await
+        .unwrap();
+    assert_eq!(inner.calls.lock().unwrap().len(), 3);
+    cache
+        .fetch_history_data("MSFT", day, start, end)
+        .await
+        .unwrap();
+    assert_eq!(inner.calls.lock().unwrap().len(), 4);
+}
+
+fn days(candles: &[MarketCandle]) -> Vec<NaiveDate> {
+    candles.iter().map(|c| c.ts.date_naive()).collect()
+}
+
+// Yahoo chart endpoint: one bar per day at the US open, within [period1, period2)
+async fn chart(
+    State(calls): State<Arc<AtomicUsize>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    let period = |name: &str| params[name].parse::<i64>().unwrap();
+    let (from, to) = (period("period1"), period("period2"));
+    let first_open = from - from.rem_euclid(86_400) + 14 * 3600 + 1800;
+    let stamps = (0..)
+        .map(|d| first_open + d * 86_400)
+        .skip_while(|ts| *ts < from)
+        .take_while(|ts| *ts < to)
+        .collect::<Vec<_>>();
+    let prices = vec![100.0; stamps.len()];
+    Json(json!({
+        "chart": {
+            "result": [{
+                "meta": {"currency": "USD", "timezone": "America/New_York", "gmtoffset": -18000},
+                "timestamp": stamps,
+                "indicators": {
+                    "quote": [{
+                        "open": prices, "high": prices, "low": prices, "close": prices,
+                        "volume": vec![1000; stamps.len()]
+                    }],
+                    "adjclose": [{"adjclose": prices}]
+                }
+            }],
+            "error": null
+        }
+    }))
+}
+
+#[tokio::test]
+async fn last_day_of_a_miss_is_cached_over_an_end_exclusive_upstream() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let router = Router::new()
+        .route("/chart/{symbol}", get(chart))
+        .with_state(calls.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/", listener.local_addr().unwrap());
+    spawn(async move { serve(listener, router).await.unwrap() });
+
+    let url = |path: &str| reqwest::Url::parse(&format!("{base}{path}")).unwrap();
+    let client = YfClient::builder()
+        .base_chart(url("chart/"))
+        .base_quote(url("quote/"))
+        .base_quote_api(url("quote-api/"))
+        .cookie_url(url("cookie"))
+        .crumb_url(url("crumb"))
+        .retry_enabled(false)
+        .build()
+        .unwrap();
+    let yahoo = YFinanceData::with_client(client, &ProviderConfig::default());
+    let cache = CachedProvider::new(Arc::new(yahoo), &CacheConfig::default());
+    let day = TickerInterval::OneDay;
+
+    let history = cache
+        .fetch_history_data("AAPL", day, date(2025, 1, 6), date(2025, 1, 8))
+        .await
+        .unwrap();
+    assert_eq!(
+        days(&history.candles),
+        [date(2025, 1, 6), date(2025, 1, 7), date(2025, 1, 8)]
+    );
+
+    // the covered last day is served from the cache with its bar
+    let last = cache
+        .fetch_history_data("AAPL", day, date(2025, 1, 8), date(2025, 1, 8))
+        .await
+        .unwrap();
+    assert_eq!(days(&last.candles), [date(2025, 1, 8)]);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use tradebox::data::md_chunks::{day_gaps, fetch_chunked, merge_chunks};
+use tradebox::data::md_provider::{
+    MarketAction, MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::requests::tk_interval::TickerInterval;
+
+fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn candle(day: NaiveDate, close: i64) -> MarketCandle {
+    let price = Decimal::from(close);
+    MarketCandle {
+        ts: day.and_time(NaiveTime::MIN).and_utc(),
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: Some(100),
+        unadjusted_close: None,
+    }
+}
+
+// Daily bars on weekdays only; like an inclusive upstream end, each answer repeats the bar after its range
+#[derive(Debug)]
+struct WeekdayProvider;
+
+#[async_trait]
+impl MarketDataProvider for WeekdayProvider {
+    fn name(&self) -> &str {
+        "weekday"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        // later chunks finish first now and then
+        let delay = u64::from(start.ordinal() % 3);
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+
+        Ok(MarketHistory {
+            candles: start
+                .iter_days()
+                .take_while(|d| *d <= end + Duration::days(1))
+                .filter(|d| d.weekday().number_from_monday() <= 5)
+                .map(|d| candle(d, i64::from(d.ordinal())))
+                .collect(),
+            actions: vec![],
+            stale: false,
+            source: None,
+            gaps: vec![],
+        })
+    }
+}
+
+#[test]
+fn merge_keeps_one_bar_per_timestamp() {
+    let jan_1 = day(2025, 1, 1);
+    let dividend = MarketAction::Dividend {
+        ts: jan_1.and_time(NaiveTime::MIN).and_utc(),
+        amount: Decimal::ONE,
+    };
+    let first = MarketHistory {
+        candles: vec![candle(jan_1, 1), candle(day(2025, 1, 2), 2)],
+        actions: vec![dividend.clone()],
+        stale: false,
+        source: None,
+        gaps: vec![],
+    };
+    let second = MarketHistory {
+        candles: vec![candle(jan_1, 10), candle(day(2025, 1, 3), 3)],
+        actions: vec![dividend.clone()],
+        stale: false,
+        source: None,
+        gaps: vec![],
+    };
+
+    let merged = merge_chunks(vec![second, first]);
+    let closes = merged.candles.iter().map(|c| c.close).collect::<Vec<_>>();
+    assert_eq!(
+        closes,
+        [Decimal::from(1), Decimal::from(2), Decimal::from(3)]
+    );
+    assert_eq!(merged.actions, [dividend]);
+}
+
+#[test]
+fn gaps_longer_than_a_closed_market_are_reported() {
+    let candles = [
+        candle(day(2025, 1, 2), 1),
+        candle(day(2025, 1, 3), 1),
+        candle(day(2025, 1, 6), 1),
+        candle(day(2025, 1, 20), 1),
+    ];
+
+    assert_eq!(
+        day_gaps(&candles, day(2025, 1, 1), day(2025, 1, 31), 4),
+        [
+            (day(2025, 1, 7), day(2025, 1, 19)),
+            (day(2025, 1, 21), day(2025, 1, 31))
+        ]
+    );
+    assert!(day_gaps(&candles, day(2025, 1, 1), day(2025, 1, 6), 4).is_empty());
+    assert_eq!(
+        day_gaps(&[], day(2025, 1, 1), day(2025, 1, 5), 4),
+        [(day(2025, 1, 1), day(2025, 1, 5))]
+    );
+}
+
+proptest! {
+    #[test]
+    fn chunked_fetch_returns_each_bar_once_in_order(
+        offset in 0i64..730,
+        len in 0i64..120,
+        chunk_days in 1i64..40,
+        concurrency in 1usize..6,
+    ) {
+        let start = day(2024, 1, 1) + Duration::days(offset);
+        let end = start + Duration::days(len);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let history = runtime.block_on(fetch_chunked(start, end, chunk_days, concurrency, |(s, e)| {
+            WeekdayProvider.fetch_history_data("AAPL", TickerInterval::OneDay, s, e)
+        })).unwrap();
+
+        let expected = start
+            .iter_days()
+            .take_while(|d| *d <= end)
+            .filter(|d| d.weekday().number_from_monday() <= 5)
+            .map(|d| candle(d, i64::from(d.ordinal())))
+            .collect::<Vec<_>>();
+        prop_assert!(history.candles.windows(2).all(|w| w[0].ts < w[1].ts));
+        prop_assert_eq!(history.candles, expected);
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tradebox::build_app_with_provider;
+use tradebox::configs::yaml_config::FailoverConfig;
+use tradebox::data::md_failover::FailoverProvider;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::data::md_store::LocalStore;
+use tradebox::data::ticker_data::TickerData;
+use tradebox::errors::md_errors::ProviderError::AllSourcesFailed;
+use tradebox::requests::tk_asset::AssetClass;
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request::TickerRequest;
+
+// One daily bar per day, or none when `empty`
+#[derive(Debug)]
+struct Source {
+    name: &'static str,
+    down: AtomicBool,
+    empty: bool,
+    calls: AtomicUsize,
+}
+
+impl Source {
+    fn new(name: &'static str) -> Arc<Self> {
+        Self::with_bars(name, true)
+    }
+
+    fn empty(name: &'static str) -> Arc<Self> {
+        Self::with_bars(name, false)
+    }
+
+    fn with_bars(name: &'static str, bars: bool) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            down: AtomicBool::new(false),
+            empty: !bars,
+            calls: AtomicUsize::new(0),
+        })
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for Source {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    // only its own failure counts as an outage
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        e.chain()
+            .any(|cause| cause.to_string() == format!("{} is down", self.name))
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.down.load(Ordering::SeqCst) {
+            return Err(anyhow!("{} is down", self.name));
+        }
+        let candles = if self.empty {
+            vec![]
+        } else {
+            start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .map(|d| MarketCandle {
+                    ts: d.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+                    open: Decimal::ONE,
+                    high: Decimal::ONE,
+                    low: Decimal::ONE,
+                    close: Decimal::ONE,
+                    volume: None,
+                    unadjusted_close: None,
+                })
+                .collect()
+        };
+        Ok(MarketHistory {
+            candles,
+            actions: vec![],
+            stale: false,
+            source: None,
+            gaps: vec![],
+        })
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn failover(
+    sources: &[Arc<Source>],
+    default: &[&str],
+    chains: &[(AssetClass, &[&str])],
+) -> Result<FailoverProvider> {
+    let sources = sources
+        .iter()
+        .map(|s| (s.name.to_string(), s.clone() as Arc<dyn MarketDataProvider>))
+        .collect::<HashMap<_, _>>();
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let config = FailoverConfig {
+        enabled: true,
+        default: names(default),
+        chains: chains
+            .iter()
+            .map(|(class, chain)| (*class, names(chain)))
+            .collect(),
+    };
+    FailoverProvider::new(&config, &sources)
+}
+
+#[test]
+fn asset_class_follows_yahoo_symbols() {
+    let cases = [
+        ("AAPL", AssetClass::Equity),
+        ("BRK-B", AssetClass::Equity),
+        ("BTC-USD", AssetClass::Crypto),
+        ("eth-eur", AssetClass::Crypto),
+        ("EUR-USD", AssetClass::Forex),
+        ("EURUSD=X", AssetClass::Forex),
+        ("^GSPC", AssetClass::Index),
+        ("GC=F", AssetClass::Future),
+    ];
+    for (ticker, class) in cases {
+        assert_eq!(AssetClass::from_ticker(ticker), class, "{ticker}");
+    }
+}
+
+#[tokio::test]
+async fn each_asset_class_walks_its_own_chain() {
+    let exchange = Source::new("exchange");
+    let yahoo = Source::new("yfinance");
+    let store = Source::new("local_store");
+    let provider = failover(
+        &[exchange.clone(), yahoo.clone(), store.clone()],
+        &["yfinance", "local_store"],
+        &[(AssetClass::Crypto, &["exchange", "yfinance"])],
+    )
+    .unwrap();
+    let (start, end) = (date(2025, 1, 1), date(2025, 1, 3));
+    let day = TickerInterval::OneDay;
+
+    let crypto = provider
+        .fetch_history_data("BTC-USD", day, start, end)
+        .await
+        .unwrap();
+    assert_eq!(crypto.source.as_deref(), Some("exchange"));
+
+    exchange.down.store(true, Ordering::SeqCst);
+    let crypto = provider
+        .fetch_history_data("BTC-USD", day, start, end)
+        .await
+        .unwrap();
+    assert_eq!(crypto.source.as_deref(), Some("yfinance"));
+    assert_eq!(crypto.candles.len(), 3);
+
+    yahoo.down.store(true, Ordering::SeqCst);
+    let equity = provider
+        .fetch_history_data("AAPL", day, start, end)
+        .await
+        .unwrap();
+    assert_eq!(equity.source.as_deref(), Some("local_store"));
+    assert_eq!(exchange.calls.load(Ordering::SeqCst), 2);
+
+    // crypto has no store in its chain
+    let error = provider
+        .fetch_history_data("BTC-USD", day, start, end)
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{error:#}").contains("tried exchange, yfinance"),
+        "{error:#}"
+    );
+    assert!(provider.is_outage(&error));
+    let rejected = anyhow!("unknown ticker").context(AllSourcesFailed {
+        ticker: "BTC-USD".into(),
+        sources: "exchange, yfinance".into(),
+        outage: false,
+    });
+    assert!(!provider.is_outage(&rejected));
+}
+
+#[tokio::test]
+async fn a_down_source_makes_an_outage_even_when_the_store_has_nothing() {
+    let yahoo = Source::new("yfinance");
+    yahoo.down.store(true, Ordering::SeqCst);
+    let dir = std::env::temp_dir().join(format!("tradebox-failover-{}", std::process::id()));
+    let store: Arc<dyn MarketDataProvider> = Arc::new(LocalStore::new(&dir));
+    let sources = HashMap::from([
+        ("yfinance".to_string(), yahoo as Arc<dyn MarketDataProvider>),
+        ("local_store".to_string(), store),
+    ]);
+    let config = FailoverConfig {
+        enabled: true,
+        default: vec!["yfinance".into()],
+        chains: HashMap::from([(
+            AssetClass::Equity,
+            vec!["yfinance".into(), "local_store".into()],
+        )]),
+    };
+    let provider = FailoverProvider::new(&config, &sources).unwrap();
+
+    // the store rejects the ticker last, but yahoo being down is what made it fail
+    let error = provider
+        .fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 3),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{error:#}").contains("No stored history"),
+        "{error:#}"
+    );
+    assert!(provider.is_outage(&error));
+}
+
+#[tokio::test]
+async fn empty_answers_fall_through_and_the_snapshot_records_the_source() {
+    let empty = Source::empty("empty");
+    let full = Source::new("full");
+    let provider = failover(&[empty.clone(), full], &["empty", "full"], &[]).unwrap();
+
+    let app = build_app_with_provider(Arc::new(provider));
+    let request = TickerRequest {
+        ticker: "AAPL".into(),
+        start: "2025-01-01".into(),
+        end: "2025-01-02".into(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: false,
+        indicators: vec![],
+    };
+    let snapshot = TickerData::new().fetch_data(&request, &app).await.unwrap();
+    assert_eq!(snapshot.candles.len(), 2);
+    assert_eq!(snapshot.source.as_deref(), Some("full"));
+    assert_eq!(empty.calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn chains_must_name_known_sources() {
+    let yahoo = Source::new("yfinance");
+    let error = failover(
+        &[yahoo],
+        &["yfinance"],
+        &[(AssetClass::Crypto, &["exchange"])],
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("exchange"));
+}
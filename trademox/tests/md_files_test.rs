@@ -0,0 +1,378 @@
+use arrow_array::{ArrayRef, Date32Array, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use chrono::{NaiveDate, TimeZone, Utc};
+use parquet::arrow::ArrowWriter;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc::channel;
+use tokio::time::timeout;
+use tradebox::build_app_with_provider;
+use tradebox::configs::yaml_config::{ColumnMapping, FilesConfig};
+use tradebox::data::md_files::FileProvider;
+use tradebox::data::md_provider::MarketDataProvider;
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request::TickerRequest;
+use tradebox::websocket::ws_codec::WireFormat;
+use tradebox::websocket::ws_protocol::FrameTag;
+use tradebox::websocket::ws_sender::spawn_stream;
+
+const DAILY_CSV: &str = "\
+time,open,high,low,close,volume
+2024-03-01,10,12,9,11,100
+2024-03-04,11,13,10,12,200
+2024-03-05,12,14,11,13,300
+";
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn files_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tradebox-files-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn files_config(dir: &Path) -> FilesConfig {
+    FilesConfig {
+        dir: dir.display().to_string(),
+        ..FilesConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn csv_maps_columns_and_reads_times_in_timezone() {
+    let dir = files_dir("mapping");
+    std::fs::write(
+        dir.join("SPY.csv"),
+        "Date;Open;High;Low;Close;Adj Close\n\
+         03/01/2024 09:30;500.5;502;499;501;500.8\n\
+         03/01/2024 09:35;501;503.25;500;502;5.0175e2\n",
+    )
+    .unwrap();
+    let config = FilesConfig {
+        interval: TickerInterval::FiveMinutes,
+        date_format: "%m/%d/%Y %H:%M".into(),
+        timezone: "America/New_York".into(),
+        delimiter: ';',
+        columns: ColumnMapping {
+            time: "Date".into(),
+            open: "Open".into(),
+            high: "High".into(),
+            low: "Low".into(),
+            close: "Adj Close".into(),
+            volume: None,
+            unadjusted_close: Some("Close".into()),
+        },
+        ..files_config(&dir)
+    };
+    let provider = FileProvider::new(&config).unwrap();
+
+    let history = provider
+        .fetch_history_data(
+            "SPY",
+            TickerInterval::FiveMinutes,
+            date(2024, 3, 1),
+            date(2024, 3, 1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(history.source.as_deref(), Some("files"));
+    assert_eq!(history.candles.len(), 2);
+
+    let first = &history.candles[0];
+    assert_eq!(
+        first.ts,
+        Utc.with_ymd_and_hms(2024, 3, 1, 14, 30, 0).unwrap()
+    );
+    assert_eq!(first.close, Decimal::new(5008, 1));
+    assert_eq!(first.unadjusted_close, Some(Decimal::from(501)));
+    assert_eq!(first.volume, None);
+    assert_eq!(history.candles[1].close, Decimal::new(50175, 2));
+
+    // the file holds 5m bars only
+    assert!(provider
+        .fetch_history_data(
+            "SPY",
+            TickerInterval::OneDay,
+            date(2024, 3, 1),
+            date(2024, 3, 1)
+        )
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn csv_history_is_limited_to_the_requested_days() {
+    let dir = files_dir("range");
+    std::fs::write(dir.join("AAPL.csv"), DAILY_CSV).unwrap();
+    let provider = FileProvider::new(&files_config(&dir)).unwrap();
+
+    let history = provider
+        .fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2024, 3, 2),
+            date(2024, 3, 5),
+        )
+        .await
+        .unwrap();
+    let days = history
+        .candles
+        .iter()
+        .map(|c| c.ts.date_naive())
+        .collect::<Vec<_>>();
+    assert_eq!(days, vec![date(2024, 3, 4), date(2024, 3, 5)]);
+    assert_eq!(history.candles[0].volume, Some(200));
+
+    let missing = provider
+        .fetch_history_data(
+            "MSFT",
+            TickerInterval::OneDay,
+            date(2024, 3, 1),
+            date(2024, 3, 5),
+        )
+        .await
+        .unwrap_err();
+    assert!(missing.to_string().contains("No dataset file for MSFT"));
+    assert!(!provider.is_outage(&missing));
+}
+
+#[tokio::test]
+async fn file_names_encode_symbols_like_the_store() {
+    let dir = files_dir("names");
+    std::fs::write(dir.join("BRK%2EB.csv"), DAILY_CSV).unwrap();
+    std::fs::write(dir.join("%5EGSPC.csv"), DAILY_CSV).unwrap();
+    let provider = FileProvider::new(&files_config(&dir)).unwrap();
+
+    for ticker in ["BRK.B", "^GSPC"] {
+        let history = provider
+            .fetch_history_data(
+                ticker,
+                TickerInterval::OneDay,
+                date(2024, 3, 1),
+                date(2024, 3, 5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(history.candles.len(), 3, "{ticker}");
+    }
+
+    let missing = provider
+        .fetch_history_data(
+            "BRK_B",
+            TickerInterval::OneDay,
+            date(2024, 3, 1),
+            date(2024, 3, 5),
+        )
+        .await
+        .unwrap_err();
+    assert!(missing.to_string().contains("No dataset file for BRK_B"));
+}
+
+// Tokyo midnight is 15:00 UTC of the day before, so the range is matched on local days
+#[tokio::test]
+async fn history_range_is_matched_on_days_in_the_dataset_timezone() {
+    let dir = files_dir("tokyo");
+    std::fs::write(dir.join("7203.csv"), DAILY_CSV).unwrap();
+    let config = FilesConfig {
+        timezone: "Asia/Tokyo".into(),
+        ..files_config(&dir)
+    };
+    let provider = FileProvider::new(&config).unwrap();
+
+    let history = provider
+        .fetch_history_data(
+            "7203",
+            TickerInterval::OneDay,
+            date(2024, 3, 1),
+            date(2024, 3, 4),
+        )
+        .await
+        .unwrap();
+    let stamps = history.candles.iter().map(|c| c.ts).collect::<Vec<_>>();
+    assert_eq!(
+        stamps,
+        vec![
+            Utc.with_ymd_and_hms(2024, 2, 29, 15, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 3, 15, 0, 0).unwrap(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn malformed_rows_are_reported_by_line() {
+    let dir = files_dir("malformed");
+    std::fs::write(
+        dir.join("AAPL.csv"),
+        "time,open,high,low,close,volume\n\
+         2024-03-01,10,12,9,11,100\n\
+         2024-03-02,10,12,9,n/a,100\n\
+         2024-03-03,10,12,9,11,100\n\
+         2024-03-04,10,8,9,9,100\n\
+         2024-03-03,10,12,9,11,100\n\
+         03/06/2024,10,12,9,11,100\n\
+         2024-03-07,10,12,9,,100\n",
+    )
+    .unwrap();
+    let provider = FileProvider::new(&files_config(&dir)).unwrap();
+
+    let e = provider
+        .fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2024, 3, 1),
+            date(2024, 3, 7),
+        )
+        .await
+        .unwrap_err();
+    let message = format!("{e:#}");
+    assert!(message.contains("5 malformed row(s)"), "{message}");
+    assert!(
+        message.contains("line 3: invalid `close` value `n/a`"),
+        "{message}"
+    );
+    assert!(
+        message.contains("line 5: high 8 is below low 9"),
+        "{message}"
+    );
+    assert!(message.contains("line 6: time 2024-03-03"), "{message}");
+    assert!(
+        message.contains("line 7: time `03/06/2024` does not match"),
+        "{message}"
+    );
+    assert!(message.contains("line 8: missing `close`"), "{message}");
+    assert!(!message.contains("line 4"), "{message}");
+}
+
+#[tokio::test]
+async fn bad_mapping_and_timezone_are_refused() {
+    let dir = files_dir("mapping-errors");
+    std::fs::write(dir.join("AAPL.csv"), DAILY_CSV).unwrap();
+
+    let config = FilesConfig {
+        timezone: "Mars/Olympus".into(),
+        ..files_config(&dir)
+    };
+    assert!(FileProvider::new(&config).is_err());
+
+    let config = FilesConfig {
+        columns: ColumnMapping {
+            time: "Date".into(),
+            ..ColumnMapping::default()
+        },
+        ..files_config(&dir)
+    };
+    let e = FileProvider::new(&config)
+        .unwrap()
+        .fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2024, 3, 1),
+            date(2024, 3, 5),
+        )
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("Missing column `Date`"));
+}
+
+#[tokio::test]
+async fn parquet_dates_read_as_local_midnight() {
+    let dir = files_dir("parquet");
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("time", DataType::Date32, false),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Utf8, true),
+        Field::new("volume", DataType::UInt64, true),
+    ]));
+    // 2024-03-01 and 2024-03-04
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Date32Array::from(vec![19783, 19786])),
+        Arc::new(Float64Array::from(vec![10.0, 11.5])),
+        Arc::new(Float64Array::from(vec![12.0, 13.0])),
+        Arc::new(Float64Array::from(vec![9.0, 10.25])),
+        Arc::new(StringArray::from(vec![Some("11"), Some("12.75")])),
+        Arc::new(UInt64Array::from(vec![Some(100), None])),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+    let file = std::fs::File::create(dir.join("BTC-USD.parquet")).unwrap();
+    let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let config = FilesConfig {
+        timezone: "Asia/Tokyo".into(),
+        ..files_config(&dir)
+    };
+    let history = FileProvider::new(&config)
+        .unwrap()
+        .fetch_history_data(
+            "BTC-USD",
+            TickerInterval::OneDay,
+            date(2024, 2, 29),
+            date(2024, 3, 5),
+        )
+        .await
+        .unwrap();
+    assert_eq!(history.candles.len(), 2);
+    assert_eq!(
+        history.candles[0].ts,
+        Utc.with_ymd_and_hms(2024, 2, 29, 15, 0, 0).unwrap()
+    );
+    assert_eq!(history.candles[1].low, Decimal::new(1025, 2));
+    assert_eq!(history.candles[1].close, Decimal::new(1275, 2));
+    assert_eq!(history.candles[0].volume, Some(100));
+    assert_eq!(history.candles[1].volume, None);
+}
+
+#[tokio::test]
+async fn file_candles_stream_over_ws() {
+    let dir = files_dir("stream");
+    std::fs::write(dir.join("AAPL.csv"), DAILY_CSV).unwrap();
+    let app = build_app_with_provider(Arc::new(FileProvider::new(&files_config(&dir)).unwrap()));
+    let req = TickerRequest {
+        ticker: "AAPL".into(),
+        start: "2024-03-01".into(),
+        end: "2024-03-05".into(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: false,
+        indicators: vec![],
+    };
+
+    let (out, mut rx) = channel(64);
+    spawn_stream(&out, &app, req, FrameTag::default(), WireFormat::Json, None);
+    let mut frames = Vec::new();
+    loop {
+        let outbound = timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let Some(frame) = outbound.into_frame() else {
+            continue;
+        };
+        let frame = serde_json::to_value(&frame).unwrap();
+        let end = frame["type"] == "snapshot_end";
+        frames.push(frame);
+        if end {
+            break;
+        }
+    }
+
+    assert_eq!(frames[0]["type"], "snapshot_begin");
+    assert_eq!(frames[0]["source"], "files");
+    let closes = frames
+        .iter()
+        .filter(|f| f["type"] == "candle")
+        .map(|f| f["candle"]["close"].clone())
+        .collect::<Vec<Value>>();
+    assert_eq!(closes.len(), 3);
+}
@@ -0,0 +1,203 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tradebox::data::md_flight::SingleFlightProvider;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::errors::md_errors::ProviderError;
+use tradebox::requests::tk_interval::TickerInterval;
+
+// Holds every fetch open for a while so concurrent callers overlap
+#[derive(Debug, Default)]
+struct SlowProvider {
+    calls: AtomicUsize,
+    fail: bool,
+}
+
+#[async_trait]
+impl MarketDataProvider for SlowProvider {
+    fn name(&self) -> &str {
+        "slow"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    // only its own typed error counts, the way real providers look at the top of the error
+    fn is_outage(&self, e: &anyhow::Error) -> bool {
+        e.downcast_ref::<ProviderError>().is_some()
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        sleep(Duration::from_millis(50)).await;
+        if self.fail {
+            return Err(ProviderError::RetriesExhausted { attempts: 3 })
+                .context("upstream throttled");
+        }
+        Ok(MarketHistory {
+            candles: start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .map(|d| MarketCandle {
+                    ts: d.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+                    open: Decimal::ONE,
+                    high: Decimal::ONE,
+                    low: Decimal::ONE,
+                    close: Decimal::ONE,
+                    volume: Some(1),
+                    unadjusted_close: None,
+                })
+                .collect(),
+            ..MarketHistory::default()
+        })
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[tokio::test]
+async fn concurrent_identical_fetches_share_one_call() {
+    let slow = Arc::new(SlowProvider::default());
+    let provider = SingleFlightProvider::new(slow.clone());
+
+    let fetches = (0..10).map(|_| {
+        provider.fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 5),
+        )
+    });
+    let results = join_all(fetches).await;
+
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(provider.coalesced(), 9);
+    let first = results[0].as_ref().unwrap();
+    assert_eq!(first.candles.len(), 5);
+    assert!(results.iter().all(|r| r.as_ref().unwrap() == first));
+
+    // finished flights are not a cache
+    provider
+        .fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 5),
+        )
+        .await
+        .unwrap();
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn different_keys_fetch_separately_and_errors_are_shared() {
+    let slow = Arc::new(SlowProvider::default());
+    let provider = SingleFlightProvider::new(slow.clone());
+
+    let (a, b) = tokio::join!(
+        provider.fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 5)
+        ),
+        provider.fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 6)
+        ),
+    );
+    assert_eq!(a.unwrap().candles.len(), 5);
+    assert_eq!(b.unwrap().candles.len(), 6);
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 2);
+
+    let failing = Arc::new(SlowProvider {
+        fail: true,
+        ..SlowProvider::default()
+    });
+    let provider = SingleFlightProvider::new(failing.clone());
+    let (a, b) = tokio::join!(
+        provider.fetch_history_data(
+            "MSFT",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 5)
+        ),
+        provider.fetch_history_data(
+            "MSFT",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 5)
+        ),
+    );
+    assert_eq!(failing.calls.load(Ordering::SeqCst), 1);
+    for e in [a.unwrap_err(), b.unwrap_err()] {
+        assert!(format!("{e:#}").contains("upstream throttled"));
+        // the typed cause survives sharing, and the inner provider still recognises it
+        assert!(e.chain().any(|cause| matches!(
+            cause.downcast_ref::<ProviderError>(),
+            Some(ProviderError::RetriesExhausted { attempts: 3 })
+        )));
+        assert!(provider.is_outage(&e));
+    }
+    assert!(!provider.is_outage(&anyhow!("bad ticker")));
+}
+
+#[tokio::test]
+async fn a_flight_abandoned_by_its_callers_is_not_resumed() {
+    let slow = Arc::new(SlowProvider::default());
+    let provider = Arc::new(SingleFlightProvider::new(slow.clone()));
+
+    let caller = provider.clone();
+    let aborted = tokio::spawn(async move {
+        caller
+            .fetch_history_data(
+                "AAPL",
+                TickerInterval::OneDay,
+                date(2025, 1, 1),
+                date(2025, 1, 5),
+            )
+            .await
+    });
+    sleep(Duration::from_millis(10)).await;
+    aborted.abort();
+    assert!(aborted.await.unwrap_err().is_cancelled());
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 1);
+
+    // the next identical fetch starts its own upstream call
+    let history = provider
+        .fetch_history_data(
+            "AAPL",
+            TickerInterval::OneDay,
+            date(2025, 1, 1),
+            date(2025, 1, 5),
+        )
+        .await
+        .unwrap();
+    assert_eq!(history.candles.len(), 5);
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(provider.coalesced(), 0);
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tradebox::build_app_with_provider;
+use tradebox::configs::yaml_config::ProviderConfig;
+use tradebox::data::md_provider::{
+    create_provider, MarketAction, MarketCandle, MarketDataProvider, MarketHistory,
+    ProviderCapabilities,
+};
+use tradebox::data::md_ranges::chunk_ranges;
+use tradebox::data::ticker_data::TickerData;
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request::TickerRequest;
+
+#[derive(Debug)]
+struct FixtureProvider;
+
+#[async_trait]
+impl MarketDataProvider for FixtureProvider {
+    fn name(&self) -> &str {
+        "fixture"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        _end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        let ts = Utc.from_utc_datetime(&start.and_hms_opt(0, 0, 0).unwrap());
+        Ok(MarketHistory {
+            candles: vec![MarketCandle {
+                ts,
+                open: Decimal::new(100, 0),
+                high: Decimal::new(110, 0),
+                low: Decimal::new(90, 0),
+                close: Decimal::new(105, 0),
+                volume: Some(1_000),
+                unadjusted_close: None,
+            }],
+            actions: vec![
+                MarketAction::Dividend {
+                    ts,
+                    amount: Decimal::new(25, 2),
+                },
+                MarketAction::Split {
+                    ts: Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap(),
+                    numerator: 4,
+                    denominator: 1,
+                },
+            ],
+            stale: false,
+            source: None,
+            gaps: vec![],
+        })
+    }
+}
+
+#[tokio::test]
+async fn fetch_data_uses_configured_provider() {
+    let app = build_app_with_provider(Arc::new(FixtureProvider));
+    let req = TickerRequest {
+        ticker: "AAPL".into(),
+        start: "2025-01-02".into(),
+        end: "2025-01-10".into(),
+        interval: "1d".into(),
+        pace: String::new(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: false,
+        indicators: vec![],
+    };
+
+    let data = TickerData::new().fetch_data(&req, &app).await.unwrap();
+
+    assert_eq!(app.provider.name(), "fixture");
+    assert_eq!(data.candles.len(), 1);
+    assert_eq!(
+        data.candles[0].time,
+        Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap()
+    );
+    assert_eq!(data.candles[0].close, Decimal::new(105, 0));
+    assert_eq!(data.candles[0].volume, Some(1_000));
+
+    // split outside the requested range is dropped
+    assert_eq!(data.events.len(), 1);
+    assert_eq!(
+        serde_json::to_value(&data.events[0]).unwrap()["type"],
+        "dividend"
+    );
+}
+
+#[test]
+fn create_provider_rejects_unknown_name() {
+    let config = ProviderConfig {
+        name: "unknown".into(),
+        ..ProviderConfig::default()
+    };
+
+    assert!(create_provider(&config).is_err());
+    assert_eq!(
+        create_provider(&ProviderConfig::default()).unwrap().name(),
+        "yfinance"
+    );
+}
+
+#[test]
+fn chunk_ranges_cover_the_range_in_order() {
+    let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+
+    assert_eq!(
+        chunk_ranges(day(1), day(20), 7),
+        [(day(1), day(7)), (day(8), day(14)), (day(15), day(20))]
+    );
+    assert_eq!(chunk_ranges(day(1), day(5), 365), [(day(1), day(5))]);
+    assert_eq!(chunk_ranges(day(5), day(5), 7), [(day(5), day(5))]);
+    assert!(chunk_ranges(day(6), day(5), 7).is_empty());
+}
@@ -0,0 +1,155 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc, Weekday};
+use rust_decimal::Decimal;
+use tradebox::data::md_provider::MarketCandle;
+use tradebox::data::md_resampler::Resampler;
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_resample::TickerResample;
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn candle(ts: DateTime<Utc>, open: i64, high: i64, low: i64, close: i64) -> MarketCandle {
+    MarketCandle {
+        ts,
+        open: Decimal::from(open),
+        high: Decimal::from(high),
+        low: Decimal::from(low),
+        close: Decimal::from(close),
+        volume: Some(100),
+        unadjusted_close: None,
+    }
+}
+
+fn daily(from: NaiveDate, to: NaiveDate) -> Vec<MarketCandle> {
+    from.iter_days()
+        .take_while(|d| *d <= to)
+        .enumerate()
+        .map(|(i, d)| {
+            let price = i as i64 + 10;
+            let ts = Utc.from_utc_datetime(&d.and_hms_opt(14, 30, 0).unwrap());
+            candle(ts, price, price + 5, price - 5, price + 1)
+        })
+        .collect()
+}
+
+#[test]
+fn resample_specs_parse_and_check_their_source() {
+    assert_eq!("W".parse::<TickerResample>().unwrap(), TickerResample::Week);
+    assert_eq!(
+        "1mo".parse::<TickerResample>().unwrap(),
+        TickerResample::Month
+    );
+    assert_eq!(
+        "q".parse::<TickerResample>().unwrap(),
+        TickerResample::Quarter
+    );
+    assert_eq!(
+        "3d".parse::<TickerResample>().unwrap(),
+        TickerResample::Days(3)
+    );
+    assert_eq!(
+        "10min".parse::<TickerResample>().unwrap(),
+        TickerResample::Minutes(10)
+    );
+    for invalid in ["1m", "0d", "5h", "2000min", ""] {
+        assert!(invalid.parse::<TickerResample>().is_err(), "{invalid}");
+    }
+
+    assert!(TickerResample::Month
+        .check_source(TickerInterval::OneDay)
+        .is_ok());
+    assert!(TickerResample::Month
+        .check_source(TickerInterval::OneHour)
+        .is_err());
+    assert!(TickerResample::Minutes(10)
+        .check_source(TickerInterval::FiveMinutes)
+        .is_ok());
+    assert!(TickerResample::Minutes(10)
+        .check_source(TickerInterval::FifteenMinutes)
+        .is_err());
+    assert!(TickerResample::Minutes(10)
+        .check_source(TickerInterval::OneDay)
+        .is_err());
+}
+
+#[test]
+fn weekly_bars_aggregate_ohlcv_from_the_anchor_day() {
+    // 2025-01-06 is a Monday
+    let candles = daily(date(2025, 1, 6), date(2025, 1, 19));
+
+    let bars = Resampler::new(TickerResample::Week, Weekday::Mon, date(2025, 1, 6))
+        .resample(candles.clone());
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0].ts, candles[0].ts);
+    assert_eq!(bars[0].open, Decimal::from(10));
+    assert_eq!(bars[0].high, Decimal::from(21));
+    assert_eq!(bars[0].low, Decimal::from(5));
+    assert_eq!(bars[0].close, Decimal::from(17));
+    assert_eq!(bars[0].volume, Some(700));
+    assert_eq!(bars[1].ts, candles[7].ts);
+
+    // fiscal weeks starting on Friday
+    let bars =
+        Resampler::new(TickerResample::Week, Weekday::Fri, date(2025, 1, 6)).resample(candles);
+    assert_eq!(bars.len(), 3);
+    assert_eq!(bars[0].volume, Some(400));
+    assert_eq!(bars[1].ts.date_naive(), date(2025, 1, 10));
+    assert_eq!(bars[2].ts.date_naive(), date(2025, 1, 17));
+}
+
+#[test]
+fn monthly_quarterly_and_n_day_bars() {
+    let candles = daily(date(2025, 1, 30), date(2025, 4, 2));
+    let origin = date(2025, 1, 30);
+
+    let months =
+        Resampler::new(TickerResample::Month, Weekday::Mon, origin).resample(candles.clone());
+    let starts = months.iter().map(|b| b.ts.date_naive()).collect::<Vec<_>>();
+    assert_eq!(
+        starts,
+        [
+            date(2025, 1, 30),
+            date(2025, 2, 1),
+            date(2025, 3, 1),
+            date(2025, 4, 1)
+        ]
+    );
+    assert_eq!(months[1].volume, Some(2800));
+
+    let quarters =
+        Resampler::new(TickerResample::Quarter, Weekday::Mon, origin).resample(candles.clone());
+    assert_eq!(quarters.len(), 2);
+    assert_eq!(quarters[1].ts.date_naive(), date(2025, 4, 1));
+    assert_eq!(quarters[0].close, candles[60].close);
+
+    // counted from the origin, so bars start on day 0, 3, 6, ...
+    let bars = Resampler::new(TickerResample::Days(3), Weekday::Mon, origin).resample(candles);
+    assert_eq!(bars[1].ts.date_naive(), date(2025, 2, 2));
+    assert_eq!(bars[1].open, Decimal::from(13));
+    assert_eq!(bars[1].close, Decimal::from(16));
+}
+
+#[test]
+fn minute_bars_group_intraday_data() {
+    let start = Utc.with_ymd_and_hms(2025, 1, 6, 14, 30, 0).unwrap();
+    let candles = (0..12)
+        .map(|i| {
+            let mut c = candle(start + chrono::Duration::minutes(5 * i), i, i + 1, i - 1, i);
+            if i == 3 {
+                c.volume = None;
+            }
+            c
+        })
+        .collect::<Vec<_>>();
+
+    let bars = Resampler::new(TickerResample::Minutes(15), Weekday::Mon, date(2025, 1, 6))
+        .resample(candles);
+    assert_eq!(bars.len(), 4);
+    assert_eq!(bars[0].ts, start);
+    assert_eq!(bars[1].open, Decimal::from(3));
+    assert_eq!(bars[1].high, Decimal::from(6));
+    assert_eq!(bars[1].low, Decimal::from(2));
+    assert_eq!(bars[1].close, Decimal::from(5));
+    assert_eq!(bars[1].volume, Some(200));
+}
@@ -0,0 +1,223 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{routing::get, serve, Json, Router};
+use chrono::NaiveDate;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::spawn;
+use tradebox::configs::yaml_config::{ProviderConfig, RateLimitConfig, RetryConfig};
+use tradebox::data::md_limiter::RateLimiter;
+use tradebox::data::md_provider::MarketDataProvider;
+use tradebox::data::md_retry::RetryPolicy;
+use tradebox::data::yfinance_data::YFinanceData;
+use tradebox::errors::md_errors::ProviderError;
+use tradebox::requests::tk_interval::TickerInterval;
+use yfinance_rs::{YfClient, YfError};
+
+// Answers chart requests with the queued statuses first, then with one daily bar
+#[derive(Debug, Default)]
+struct StubYahoo {
+    statuses: Mutex<VecDeque<u16>>,
+    chart_calls: AtomicUsize,
+}
+
+async fn chart(State(stub): State<Arc<StubYahoo>>) -> Response {
+    stub.chart_calls.fetch_add(1, Ordering::SeqCst);
+    let status = stub.statuses.lock().unwrap().pop_front();
+    if let Some(status) = status {
+        return StatusCode::from_u16(status).unwrap().into_response();
+    }
+    Json(json!({
+        "chart": {
+            "result": [{
+                "meta": {"currency": "USD", "timezone": "America/New_York", "gmtoffset": -18000},
+                "timestamp": [1735828200],
+                "indicators": {
+                    "quote": [{
+                        "open": [100.0],
+                        "high": [102.0],
+                        "low": [99.0],
+                        "close": [101.0],
+                        "volume": [1000]
+                    }],
+                    "adjclose": [{"adjclose": [101.0]}]
+                }
+            }],
+            "error": null
+        }
+    }))
+    .into_response()
+}
+
+async fn start_stub(statuses: &[u16]) -> (Arc<StubYahoo>, YfClient) {
+    let stub = Arc::new(StubYahoo {
+        statuses: Mutex::new(statuses.iter().copied().collect()),
+        ..StubYahoo::default()
+    });
+    let router = Router::new()
+        .route("/chart/{symbol}", get(chart))
+        .with_state(stub.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/", listener.local_addr().unwrap());
+    spawn(async move { serve(listener, router).await.unwrap() });
+
+    // profile and auth lookups hit the stub too and fall back on its 404s
+    let url = |path: &str| reqwest::Url::parse(&format!("{base}{path}")).unwrap();
+    let client = YfClient::builder()
+        .base_chart(url("chart/"))
+        .base_quote(url("quote/"))
+        .base_quote_api(url("quote-api/"))
+        .cookie_url(url("cookie"))
+        .crumb_url(url("crumb"))
+        .retry_enabled(false)
+        .build()
+        .unwrap();
+    (stub, client)
+}
+
+fn config(max_retries: u32) -> ProviderConfig {
+    ProviderConfig {
+        retry: RetryConfig {
+            max_retries,
+            base_delay_ms: 10,
+            max_delay_ms: 50,
+            jitter: true,
+        },
+        rate_limit: RateLimitConfig {
+            requests_per_sec: 0.0,
+            burst: 1,
+        },
+        ..ProviderConfig::default()
+    }
+}
+
+async fn fetch(provider: &YFinanceData) -> anyhow::Result<usize> {
+    let day = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+    let history = provider
+        .fetch_history_data("AAPL", TickerInterval::OneDay, day, day)
+        .await?;
+    Ok(history.candles.len())
+}
+
+#[tokio::test]
+async fn rate_limited_and_server_errors_are_retried() {
+    let (stub, client) = start_stub(&[429, 503]).await;
+    let provider = YFinanceData::with_client(client, &config(3));
+
+    assert_eq!(fetch(&provider).await.unwrap(), 1);
+    assert_eq!(stub.chart_calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn retries_stop_at_the_configured_limit() {
+    let (stub, client) = start_stub(&[500, 502, 503, 504]).await;
+    let provider = YFinanceData::with_client(client, &config(2));
+
+    let error = format!("{:#}", fetch(&provider).await.unwrap_err());
+    assert!(error.contains("gave up after 3 attempt(s)"), "{error}");
+    assert!(error.contains("Server error 503"), "{error}");
+    assert_eq!(stub.chart_calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let (stub, client) = start_stub(&[404]).await;
+    let provider = YFinanceData::with_client(client, &config(3));
+
+    let error = fetch(&provider).await.unwrap_err();
+    assert!(
+        !error.chain().any(|cause| cause.is::<ProviderError>()),
+        "{error:#}"
+    );
+    assert!(
+        error.chain().any(|cause| matches!(
+            cause.downcast_ref::<YfError>(),
+            Some(YfError::NotFound { .. })
+        )),
+        "{error:#}"
+    );
+    assert!(!provider.is_outage(&error));
+    assert_eq!(stub.chart_calls.load(Ordering::SeqCst), 1);
+}
+
+// The stub has a single bar on Jan 2, so the rest of the range is a gap
+#[tokio::test]
+async fn days_without_bars_are_returned_as_gaps() {
+    let (_stub, client) = start_stub(&[]).await;
+    let provider = YFinanceData::with_client(client, &config(0));
+    let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+
+    let history = provider
+        .fetch_history_data("AAPL", TickerInterval::OneDay, day(2), day(10))
+        .await
+        .unwrap();
+    assert_eq!(history.candles.len(), 1);
+    assert_eq!(history.gaps, [(day(3), day(10))]);
+}
+
+#[tokio::test]
+async fn every_attempt_passes_the_rate_limiter() {
+    let (stub, client) = start_stub(&[429, 429]).await;
+    let provider = YFinanceData::with_client(
+        client,
+        &ProviderConfig {
+            rate_limit: RateLimitConfig {
+                requests_per_sec: 10.0,
+                burst: 1,
+            },
+            ..config(3)
+        },
+    );
+
+    let started = Instant::now();
+    assert_eq!(fetch(&provider).await.unwrap(), 1);
+    assert_eq!(stub.chart_calls.load(Ordering::SeqCst), 3);
+    assert!(started.elapsed() >= Duration::from_millis(180));
+}
+
+#[tokio::test]
+async fn token_bucket_allows_a_burst_then_the_rate() {
+    let limiter = RateLimiter::new(20.0, 3);
+
+    let started = Instant::now();
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+    assert!(started.elapsed() < Duration::from_millis(40));
+
+    for _ in 0..4 {
+        limiter.acquire().await;
+    }
+    assert!(started.elapsed() >= Duration::from_millis(190));
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap_with_jitter_in_the_upper_half() {
+    let config = RetryConfig {
+        max_retries: 5,
+        base_delay_ms: 100,
+        max_delay_ms: 1000,
+        jitter: false,
+    };
+    let exact = RetryPolicy::from(&config);
+    let delays = (0..5).map(|retry| exact.delay(retry)).collect::<Vec<_>>();
+    assert_eq!(
+        delays,
+        [100, 200, 400, 800, 1000].map(Duration::from_millis)
+    );
+
+    let jittered = RetryPolicy::from(&RetryConfig {
+        jitter: true,
+        ..config
+    });
+    for retry in 0..5 {
+        let full = exact.delay(retry);
+        let delay = jittered.delay(retry);
+        assert!(delay >= full / 2 && delay <= full, "{delay:?} vs {full:?}");
+    }
+}
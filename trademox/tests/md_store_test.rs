@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tradebox::configs::yaml_config::StoreMode;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::data::md_store::{LocalStore, StoredProvider};
+use tradebox::requests::tk_interval::TickerInterval;
+
+#[derive(Debug, Default)]
+struct NetworkProvider {
+    calls: AtomicUsize,
+    down: bool,
+}
+
+#[async_trait]
+impl MarketDataProvider for NetworkProvider {
+    fn name(&self) -> &str {
+        "network"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.down {
+            return Err(anyhow!("network down"));
+        }
+        let candles = start
+            .iter_days()
+            .take_while(|d| *d <= end)
+            .map(|d| MarketCandle {
+                ts: Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap()),
+                open: Decimal::TEN,
+                high: Decimal::TEN,
+                low: Decimal::TEN,
+                close: Decimal::TEN,
+                volume: Some(10),
+                unadjusted_close: None,
+            })
+            .collect();
+        Ok(MarketHistory {
+            candles,
+            actions: vec![],
+            stale: false,
+            source: None,
+            gaps: vec![],
+        })
+    }
+}
+
+fn store_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("tradebox-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[tokio::test]
+async fn cache_first_persists_and_offline_reads_back() {
+    let dir = store_dir("cache-first");
+    let day = TickerInterval::OneDay;
+    let network = Arc::new(NetworkProvider::default());
+    let store = Arc::new(LocalStore::new(&dir));
+    let provider = StoredProvider::new(network.clone(), store, StoreMode::CacheFirst);
+
+    provider
+        .fetch_history_data("BTC-USD", day, date(2024, 3, 1), date(2024, 3, 5))
+        .await
+        .unwrap();
+    provider
+        .fetch_history_data("BTC-USD", day, date(2024, 3, 2), date(2024, 3, 4))
+        .await
+        .unwrap();
+    assert_eq!(network.calls.load(Ordering::SeqCst), 1);
+
+    // a fresh store over the same directory simulates a restart without network
+    let offline = StoredProvider::new(
+        Arc::new(NetworkProvider {
+            down: true,
+            ..Default::default()
+        }),
+        Arc::new(LocalStore::new(&dir)),
+        StoreMode::OfflineOnly,
+    );
+    let history = offline
+        .fetch_history_data("BTC-USD", day, date(2024, 3, 1), date(2024, 3, 5))
+        .await
+        .unwrap();
+    assert_eq!(history.candles.len(), 5);
+
+    assert!(offline
+        .fetch_history_data("ETH-USD", day, date(2024, 3, 1), date(2024, 3, 5))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn network_first_falls_back_to_store() {
+    let dir = store_dir("network-first");
+    let day = TickerInterval::OneDay;
+    let store = Arc::new(LocalStore::new(&dir));
+
+    StoredProvider::new(
+        Arc::new(NetworkProvider::default()),
+        store.clone(),
+        StoreMode::NetworkFirst,
+    )
+    .fetch_history_data("AAPL", day, date(2024, 3, 1), date(2024, 3, 3))
+    .await
+    .unwrap();
+
+    let down = StoredProvider::new(
+        Arc::new(NetworkProvider {
+            down: true,
+            ..Default::default()
+        }),
+        store,
+        StoreMode::NetworkFirst,
+    );
+    let history = down
+        .fetch_history_data("AAPL", day, date(2024, 3, 1), date(2024, 3, 3))
+        .await
+        .unwrap();
+    assert_eq!(history.candles.len(), 3);
+}
+
+#[tokio::test]
+async fn tickers_with_symbols_get_their_own_files() {
+    let dir = store_dir("symbols");
+    let day = TickerInterval::OneDay;
+    let store = Arc::new(LocalStore::new(&dir));
+    let network = StoredProvider::new(
+        Arc::new(NetworkProvider::default()),
+        store.clone(),
+        StoreMode::CacheFirst,
+    );
+
+    // each ticker stores two days of its own
+    let tickers = ["BRK.B", "BRK_B", "^GSPC", "_GSPC"];
+    for (i, ticker) in tickers.iter().enumerate() {
+        let first = date(2024, 3, 1 + 5 * i as u32);
+        network
+            .fetch_history_data(ticker, day, first, first + chrono::Duration::days(1))
+            .await
+            .unwrap();
+    }
+
+    let offline = StoredProvider::new(
+        Arc::new(NetworkProvider {
+            down: true,
+            ..Default::default()
+        }),
+        store,
+        StoreMode::OfflineOnly,
+    );
+    for (i, ticker) in tickers.iter().enumerate() {
+        let history = offline
+            .fetch_history_data(ticker, day, date(2024, 3, 1), date(2024, 3, 31))
+            .await
+            .unwrap();
+        let days = history
+            .candles
+            .iter()
+            .map(|c| c.ts.date_naive())
+            .collect::<Vec<_>>();
+        let first = date(2024, 3, 1 + 5 * i as u32);
+        assert_eq!(days, [first, first + chrono::Duration::days(1)], "{ticker}");
+    }
+    assert!(dir.join("BRK%2EB").join("1d.json").is_file());
+    assert!(!dir.join("BRK%2EB").join("1d.json.tmp").exists());
+}
@@ -0,0 +1,276 @@
+mod common;
+
+use common::{start_test_server, DayProvider};
+
+use axum::http::{HeaderValue, StatusCode};
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tradebox::configs::yaml_config::{CacheConfig, FilesConfig, StoreMode};
+use tradebox::data::md_cache::CachedProvider;
+use tradebox::data::md_files::FileProvider;
+use tradebox::data::md_store::{LocalStore, StoredProvider};
+use tradebox::rest::rest_history::{HistoryFormat, GAPS_HEADER, STALE_HEADER};
+
+async fn get_history(addr: &str, query: &str, accept: Option<&str>) -> reqwest::Response {
+    let mut request = reqwest::Client::new().get(format!("http://{addr}/v1/history?{query}"));
+    if let Some(accept) = accept {
+        request = request.header("Accept", accept);
+    }
+    request.send().await.unwrap()
+}
+
+#[test]
+fn accept_header_picks_first_supported_format() {
+    let format =
+        |accept: &'static str| HistoryFormat::from_accept(Some(&HeaderValue::from_static(accept)));
+
+    assert_eq!(HistoryFormat::from_accept(None), Some(HistoryFormat::Json));
+    assert_eq!(format("text/csv"), Some(HistoryFormat::Csv));
+    assert_eq!(
+        format("text/html, text/csv;q=0.9, application/json"),
+        Some(HistoryFormat::Csv)
+    );
+    assert_eq!(format("text/csv;q=0, */*"), Some(HistoryFormat::Json));
+    assert_eq!(
+        format("application/vnd.apache.arrow.stream"),
+        Some(HistoryFormat::Arrow)
+    );
+    assert_eq!(format("image/png"), None);
+}
+
+#[tokio::test]
+async fn history_is_served_as_json_and_csv() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let query = "ticker=AAPL&start=2025-01-01&end=2025-01-03&interval=1d";
+
+    let response = get_history(&addr, query, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let candles: Value = response.json().await.unwrap();
+    assert_eq!(candles.as_array().unwrap().len(), 3);
+    assert_eq!(candles[0]["time"], "2025-01-01T00:00:00Z");
+    assert_eq!(candles[2]["close"], "3");
+
+    let response = get_history(&addr, query, Some("text/csv")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/csv");
+    let csv = response.text().await.unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "time,open,high,low,close,volume,unadjusted_close");
+    assert_eq!(lines[1], "2025-01-01T00:00:00+00:00,1,2,0,1,100,");
+    assert_eq!(lines.len(), 4);
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn yahoo_symbols_of_every_asset_class_are_accepted() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+
+    for ticker in [
+        "EURUSD%3DX",
+        "GC%3DF",
+        "%5EGSPC",
+        "BRK.B",
+        "7203.T",
+        "BTC-USD",
+    ] {
+        let query = format!("ticker={ticker}&start=2025-01-01&end=2025-01-02");
+        let response = get_history(&addr, &query, None).await;
+        assert_eq!(response.status(), StatusCode::OK, "{ticker}");
+    }
+    for ticker in ["^", "%5E%5EGSPC", "EUR%2FUSD", "brk.b"] {
+        let query = format!("ticker={ticker}&start=2025-01-01&end=2025-01-02");
+        let response = get_history(&addr, &query, None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{ticker}");
+    }
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn history_errors_use_api_error_format() {
+    let provider = Arc::new(DayProvider::default());
+    let (addr, shutdown_tx) = start_test_server(provider.clone()).await;
+
+    let cases = [
+        (
+            "ticker=bad$&start=2025-01-01&end=2025-01-03",
+            None,
+            StatusCode::BAD_REQUEST,
+            "InvalidTicker",
+        ),
+        (
+            "ticker=AAPL&start=2025-01-01&end=2025-01-03&interval=2h",
+            None,
+            StatusCode::BAD_REQUEST,
+            "InvalidInterval",
+        ),
+        (
+            "ticker=AAPL&start=2025-01-05&end=2025-01-03",
+            None,
+            StatusCode::BAD_REQUEST,
+            "InvalidDate",
+        ),
+        (
+            "ticker=AAPL&start=2025-01-01&end=2025-01-03",
+            Some("image/png"),
+            StatusCode::NOT_ACCEPTABLE,
+            "NotAcceptable",
+        ),
+    ];
+    for (query, accept, status, error) in cases {
+        let response = get_history(&addr, query, accept).await;
+        assert_eq!(response.status(), status, "{query}");
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"], error, "{query}");
+    }
+
+    provider.down.store(true, Ordering::SeqCst);
+    let response = get_history(&addr, "ticker=AAPL&start=2025-01-01&end=2025-01-03", None).await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "FetchFailed");
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn stale_history_names_its_gaps() {
+    let provider = Arc::new(DayProvider::default());
+    let cache = CachedProvider::new(provider.clone(), &CacheConfig::default());
+    let (addr, shutdown_tx) = start_test_server(Arc::new(cache)).await;
+
+    let response = get_history(&addr, "ticker=AAPL&start=2025-01-05&end=2025-01-10", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(GAPS_HEADER).is_none());
+
+    provider.down.store(true, Ordering::SeqCst);
+    let response = get_history(&addr, "ticker=AAPL&start=2025-01-01&end=2025-01-10", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[STALE_HEADER], "true");
+    assert_eq!(response.headers()[GAPS_HEADER], "2025-01-01/2025-01-04");
+    let candles: Value = response.json().await.unwrap();
+    assert_eq!(candles.as_array().unwrap().len(), 6);
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn history_indicators_are_warmed_up_before_start() {
+    let provider = Arc::new(DayProvider::default());
+    let (addr, shutdown_tx) = start_test_server(provider.clone()).await;
+
+    let query = "ticker=AAPL&start=2025-01-10&end=2025-01-12&indicators=sma:3,ema:2";
+    let response = get_history(&addr, query, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let candles: Value = response.json().await.unwrap();
+    assert_eq!(candles.as_array().unwrap().len(), 3);
+    assert_eq!(candles[0]["time"], "2025-01-10T00:00:00Z");
+    assert_eq!(candles[0]["indicators"]["sma_3"], 9.0);
+    assert_eq!(candles[2]["indicators"]["sma_3"], 11.0);
+    assert!(candles[0]["indicators"]["ema_2"].is_f64());
+
+    let fetch_start = provider.starts.lock().unwrap()[0];
+    assert!(fetch_start < NaiveDate::from_ymd_opt(2025, 1, 10).unwrap());
+
+    let response = get_history(
+        &addr,
+        "ticker=AAPL&start=2025-01-10&end=2025-01-12&indicators=wma:3",
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "InvalidQuery");
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn history_is_resampled_to_weekly_bars() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+
+    // 2025-01-06 is a Monday
+    let query = "ticker=AAPL&start=2025-01-06&end=2025-01-19&resample=W";
+    let response = get_history(&addr, query, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let candles: Value = response.json().await.unwrap();
+    assert_eq!(candles.as_array().unwrap().len(), 2);
+    assert_eq!(candles[0]["time"], "2025-01-06T00:00:00Z");
+    assert_eq!(candles[0]["open"], "6");
+    assert_eq!(candles[0]["high"], "13");
+    assert_eq!(candles[0]["low"], "5");
+    assert_eq!(candles[0]["close"], "12");
+    assert_eq!(candles[0]["volume"], 700);
+
+    let response = get_history(
+        &addr,
+        "ticker=AAPL&start=2025-01-06&end=2025-01-19&interval=1h&resample=M",
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "InvalidResample");
+
+    let _ = shutdown_tx.send(());
+}
+
+// Tokyo midnight is 15:00 UTC of the day before; stored and cached reads must match the same days
+#[tokio::test]
+async fn cached_dataset_days_stay_in_the_dataset_timezone() {
+    let dir = std::env::temp_dir().join(format!("tradebox-rest-tokyo-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let files_dir = dir.join("files");
+    std::fs::create_dir_all(&files_dir).unwrap();
+    std::fs::write(
+        files_dir.join("7203%2ET.csv"),
+        "time,open,high,low,close,volume\n\
+         2024-03-01,10,12,9,11,100\n\
+         2024-03-04,11,13,10,12,200\n\
+         2024-03-05,12,14,11,13,300\n",
+    )
+    .unwrap();
+    let files = FileProvider::new(&FilesConfig {
+        dir: files_dir.display().to_string(),
+        timezone: "Asia/Tokyo".into(),
+        ..FilesConfig::default()
+    })
+    .unwrap();
+    let stored = StoredProvider::new(
+        Arc::new(files),
+        Arc::new(LocalStore::new(dir.join("store"))),
+        StoreMode::CacheFirst,
+    );
+    let cache = CachedProvider::new(Arc::new(stored), &CacheConfig::default());
+    let (addr, shutdown_tx) = start_test_server(Arc::new(cache)).await;
+
+    let times = |candles: Value| {
+        candles
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["time"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    // fetched, then read back from the cache
+    for _ in 0..2 {
+        let response =
+            get_history(&addr, "ticker=7203.T&start=2024-03-01&end=2024-03-04", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            times(response.json().await.unwrap()),
+            vec!["2024-02-29T15:00:00Z", "2024-03-03T15:00:00Z"]
+        );
+    }
+    let response = get_history(&addr, "ticker=7203.T&start=2024-03-01&end=2024-03-01", None).await;
+    assert_eq!(
+        times(response.json().await.unwrap()),
+        vec!["2024-02-29T15:00:00Z"]
+    );
+
+    let _ = shutdown_tx.send(());
+    let _ = std::fs::remove_dir_all(&dir);
+}
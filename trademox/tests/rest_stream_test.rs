@@ -0,0 +1,103 @@
+mod common;
+
+use common::{start_test_server, DayProvider};
+
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::sync::Arc;
+
+// (id, data) of every event in a finished SSE body
+fn parse_events(body: &str) -> Vec<(Option<String>, Value)> {
+    body.split("\n\n")
+        .filter_map(|block| {
+            let mut id = None;
+            let mut data = None;
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("id:") {
+                    id = Some(value.trim().to_string());
+                }
+                if let Some(value) = line.strip_prefix("data:") {
+                    data = Some(serde_json::from_str(value.trim()).unwrap());
+                }
+            }
+            Some((id, data?))
+        })
+        .collect()
+}
+
+// Event id of the daily bar of 2025-01-`day`
+fn bar_id(day: u32) -> String {
+    NaiveDate::from_ymd_opt(2025, 1, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis()
+        .to_string()
+}
+
+async fn get_stream(addr: &str, query: &str, last_event_id: Option<&str>) -> reqwest::Response {
+    let mut request = reqwest::Client::new().get(format!("http://{addr}/v1/stream?{query}"));
+    if let Some(id) = last_event_id {
+        request = request.header("Last-Event-ID", id);
+    }
+    request.send().await.unwrap()
+}
+
+#[tokio::test]
+async fn stream_emits_the_socket_frame_sequence() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let query = "ticker=AAPL&start=2025-01-01&end=2025-01-03&pace=burst";
+
+    let response = get_stream(&addr, query, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let events = parse_events(&response.text().await.unwrap());
+    let frames = events
+        .iter()
+        .map(|(id, data)| (id.clone(), data["type"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        frames,
+        vec![
+            (None, "snapshot_begin"),
+            (Some(bar_id(1)), "candle"),
+            (Some(bar_id(2)), "candle"),
+            (Some(bar_id(3)), "candle"),
+            (None, "snapshot_end")
+        ]
+    );
+    assert_eq!(events[3].1["seq"], 3);
+    assert_eq!(events[3].1["candle"]["close"], "3");
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn last_event_id_resumes_after_the_seen_bar() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let query = "ticker=AAPL&start=2025-01-01&end=2025-01-05&pace=rate:20";
+
+    let response = get_stream(&addr, query, Some(&bar_id(3))).await;
+    let events = parse_events(&response.text().await.unwrap());
+    let ids = events
+        .iter()
+        .filter_map(|(id, _)| id.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![bar_id(4), bar_id(5)]);
+    assert_eq!(events[1].1["candle"]["close"], "4");
+    assert_eq!(events.first().unwrap().1["type"], "snapshot_begin");
+    assert_eq!(events.last().unwrap().1["type"], "snapshot_end");
+
+    let response = get_stream(&addr, query, Some("abc")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = get_stream(&addr, &format!("{query}&delivery=arrow"), None).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "InvalidDelivery");
+
+    let _ = shutdown_tx.send(());
+}
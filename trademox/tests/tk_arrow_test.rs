@@ -0,0 +1,58 @@
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float64Type, TimestampMillisecondType, UInt64Type};
+use arrow_array::Array;
+use arrow_ipc::reader::StreamReader;
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use tradebox::creator::tk_arrow::{candle_schema, candles_to_arrow_ipc};
+use tradebox::creator::tk_creator::TickerParams;
+
+fn candle(day: u32, volume: Option<u64>) -> TickerParams {
+    TickerParams {
+        time: Utc.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap(),
+        open: Decimal::new(10050, 2),
+        high: Decimal::new(10100, 2),
+        low: Decimal::new(9900, 2),
+        close: Decimal::new(10025, 2),
+        volume,
+        unadjusted_close: volume.map(|_| Decimal::new(40100, 2)),
+        indicators: Default::default(),
+    }
+}
+
+#[test]
+fn arrow_ipc_round_trips_candles() {
+    let candles = vec![candle(2, Some(1_000)), candle(3, None)];
+    let bytes = candles_to_arrow_ipc(&candles).unwrap();
+
+    let mut reader = StreamReader::try_new(bytes.as_slice(), None).unwrap();
+    assert_eq!(reader.schema().as_ref(), &candle_schema());
+
+    let batch = reader.next().unwrap().unwrap();
+    assert!(reader.next().is_none());
+    assert_eq!(batch.num_rows(), 2);
+
+    let time = batch.column(0).as_primitive::<TimestampMillisecondType>();
+    assert_eq!(time.value(1), candles[1].time.timestamp_millis());
+    assert_eq!(
+        batch.column(4).as_primitive::<Float64Type>().value(0),
+        100.25
+    );
+
+    let volume = batch.column(5).as_primitive::<UInt64Type>();
+    assert_eq!(volume.value(0), 1_000);
+    assert!(volume.is_null(1));
+
+    let unadjusted_close = batch.column(6).as_primitive::<Float64Type>();
+    assert_eq!(unadjusted_close.value(0), 401.0);
+    assert!(unadjusted_close.is_null(1));
+}
+
+#[test]
+fn empty_history_keeps_the_schema() {
+    let bytes = candles_to_arrow_ipc(&[]).unwrap();
+    let mut reader = StreamReader::try_new(bytes.as_slice(), None).unwrap();
+
+    assert_eq!(reader.schema().fields().len(), 7);
+    assert_eq!(reader.next().unwrap().unwrap().num_rows(), 0);
+}
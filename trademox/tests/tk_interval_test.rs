@@ -0,0 +1,59 @@
+use chrono::{Duration, NaiveDate};
+use tradebox::data::md_ranges::chunk_ranges;
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request_validator::{ensure_interval_fmt, validate_interval};
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+}
+
+#[test]
+fn intervals_parse_from_their_names() {
+    for interval in TickerInterval::ALL {
+        assert_eq!(
+            interval.as_str().parse::<TickerInterval>().unwrap(),
+            interval
+        );
+    }
+    assert_eq!(
+        "1h".parse::<TickerInterval>().unwrap(),
+        TickerInterval::OneHour
+    );
+    assert!(TickerInterval::FiveMinutes.is_intraday());
+    assert!(!TickerInterval::OneDay.is_intraday());
+
+    for bad in ["1H", "2m", "60m", "daily", " 1d"] {
+        assert!(bad.parse::<TickerInterval>().is_err(), "{bad}");
+        assert!(ensure_interval_fmt(bad).is_err(), "{bad}");
+    }
+    // an empty interval falls back to the default
+    assert!(validate_interval("").is_ok());
+    assert_eq!(TickerInterval::default(), TickerInterval::OneDay);
+}
+
+#[test]
+fn chunks_follow_the_interval_limit() {
+    let chunks = chunk_ranges(day(1), day(20), TickerInterval::OneMinute.chunk_days());
+    assert_eq!(
+        chunks,
+        [(day(1), day(7)), (day(8), day(14)), (day(15), day(20))]
+    );
+
+    // one day is one chunk, for intraday and daily bars alike
+    for interval in TickerInterval::ALL {
+        assert_eq!(
+            chunk_ranges(day(9), day(9), interval.chunk_days()),
+            [(day(9), day(9))]
+        );
+    }
+
+    let end = day(1) + Duration::days(1000);
+    for interval in TickerInterval::ALL {
+        let chunks = chunk_ranges(day(1), end, interval.chunk_days());
+        assert_eq!(chunks.first().unwrap().0, day(1));
+        assert_eq!(chunks.last().unwrap().1, end);
+        assert!(chunks
+            .iter()
+            .all(|(s, e)| (*e - *s).num_days() < interval.chunk_days()));
+    }
+}
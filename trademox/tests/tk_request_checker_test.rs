@@ -14,6 +14,13 @@ fn validates_date_format_env() {
         ticker: def_ticker,
         start: def_start,
         end: "bad-date".into(),
+        interval: "1d".into(),
+        pace: String::new(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: false,
+        indicators: vec![],
     };
 
     assert!(get_default_or_start(&req).is_ok());
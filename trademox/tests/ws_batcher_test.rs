@@ -0,0 +1,95 @@
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde_json::to_value;
+use tradebox::creator::tk_creator::TickerParams;
+use tradebox::requests::tk_batch::TickerBatch;
+use tradebox::websocket::ws_batcher::CandleBatcher;
+use tradebox::websocket::ws_codec::WireFormat;
+use tradebox::websocket::ws_protocol::FrameBody;
+
+fn candle(day: u32) -> TickerParams {
+    TickerParams {
+        time: Utc.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap(),
+        open: Decimal::from(day),
+        high: Decimal::from(day),
+        low: Decimal::from(day),
+        close: Decimal::from(day),
+        volume: Some(100),
+        unadjusted_close: None,
+        indicators: Default::default(),
+    }
+}
+
+fn frame_len(body: Option<FrameBody>) -> usize {
+    match body {
+        Some(FrameBody::Candles { candles }) => candles.len(),
+        other => panic!("expected a candles frame, got {other:?}"),
+    }
+}
+
+#[test]
+fn batch_parses_and_round_trips() {
+    for batch in ["single", "count:100", "bytes:65536"] {
+        assert_eq!(batch.parse::<TickerBatch>().unwrap().to_string(), batch);
+    }
+    for batch in ["", "count", "count:0", "bytes:-1", "rows:10"] {
+        assert!(
+            batch.parse::<TickerBatch>().is_err(),
+            "{batch} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn single_batch_sends_one_candle_per_frame() {
+    let mut batcher = CandleBatcher::new("", WireFormat::Json).unwrap();
+    let body = batcher.push(candle(1)).unwrap();
+
+    assert_eq!(to_value(body.unwrap()).unwrap()["type"], "candle");
+    assert!(batcher.flush().is_none());
+}
+
+#[test]
+fn count_batch_groups_candles() {
+    let mut batcher = CandleBatcher::new("count:2", WireFormat::Json).unwrap();
+
+    assert!(batcher.push(candle(1)).unwrap().is_none());
+    assert_eq!(frame_len(batcher.push(candle(2)).unwrap()), 2);
+    assert!(batcher.push(candle(3)).unwrap().is_none());
+    assert_eq!(frame_len(batcher.flush()), 1);
+    assert!(batcher.flush().is_none());
+}
+
+#[test]
+fn bytes_batch_stays_within_budget() {
+    let size = serde_json::to_vec(&candle(1)).unwrap().len() + 1;
+    let mut batcher = CandleBatcher::new(&format!("bytes:{}", size * 3), WireFormat::Json).unwrap();
+
+    assert!(batcher.push(candle(1)).unwrap().is_none());
+    assert!(batcher.push(candle(2)).unwrap().is_none());
+    assert!(batcher.push(candle(3)).unwrap().is_none());
+    assert_eq!(frame_len(batcher.push(candle(4)).unwrap()), 3);
+    assert_eq!(frame_len(batcher.flush()), 1);
+}
+
+#[test]
+fn bytes_batch_is_measured_in_the_session_format() {
+    let json = serde_json::to_vec(&candle(1)).unwrap().len() + 1;
+    let budget = format!("bytes:{}", json * 6);
+    let batched = |format: WireFormat| {
+        let mut batcher = CandleBatcher::new(&budget, format).unwrap();
+        loop {
+            if let Some(body) = batcher.push(candle(1)).unwrap() {
+                return frame_len(Some(body));
+            }
+        }
+    };
+
+    assert_eq!(batched(WireFormat::Json), 6);
+    for format in [WireFormat::MsgPack, WireFormat::Cbor] {
+        let size = format.encoded_len(&candle(1)).unwrap();
+        // binary candles differ in size, so the same budget holds a different count
+        assert_eq!(batched(format), json * 6 / size, "{format:?}");
+        assert_ne!(batched(format), 6, "{format:?}");
+    }
+}
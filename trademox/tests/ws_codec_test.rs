@@ -0,0 +1,147 @@
+mod common;
+
+use common::{start_test_server, DayProvider};
+
+use axum::extract::ws::Message as AxumMessage;
+use axum::http::HeaderValue;
+use chrono::{TimeZone, Utc};
+use futures_util::StreamExt;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc::channel;
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+use tradebox::build_app_with_provider;
+use tradebox::creator::tk_creator::TickerParams;
+use tradebox::requests::tk_request::TickerRequest;
+use tradebox::websocket::ws_codec::WireFormat;
+use tradebox::websocket::ws_protocol::{FrameBody, FrameTag, ServerFrame};
+use tradebox::websocket::ws_sender::{spawn_stream, Outbound};
+
+fn candle_frame() -> ServerFrame {
+    ServerFrame {
+        tag: FrameTag::for_request(Some("r-1".into())),
+        seq: Some(1),
+        cursor: None,
+        body: FrameBody::Candle {
+            candle: TickerParams {
+                time: Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap(),
+                open: Decimal::new(24893, 2),
+                high: Decimal::new(24910, 2),
+                low: Decimal::new(24182, 2),
+                close: Decimal::new(24385, 2),
+                volume: Some(55_740_700),
+                unadjusted_close: None,
+                indicators: Default::default(),
+            },
+        },
+    }
+}
+
+#[test]
+fn subprotocol_selects_wire_format() {
+    let format = |p: &'static str| WireFormat::from_subprotocol(Some(&HeaderValue::from_static(p)));
+
+    assert_eq!(format("trademox.msgpack"), WireFormat::MsgPack);
+    assert_eq!(format("trademox.cbor"), WireFormat::Cbor);
+    assert_eq!(format("trademox.json"), WireFormat::Json);
+    assert_eq!(WireFormat::from_subprotocol(None), WireFormat::Json);
+}
+
+#[test]
+fn binary_formats_encode_prices_as_numbers() {
+    let AxumMessage::Text(text) = WireFormat::Json.encode(&candle_frame()).unwrap() else {
+        panic!("JSON frames are text");
+    };
+    let json: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(json["candle"]["close"], "243.85");
+
+    let AxumMessage::Binary(bytes) = WireFormat::MsgPack.encode(&candle_frame()).unwrap() else {
+        panic!("MessagePack frames are binary");
+    };
+    let msgpack: Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(msgpack["type"], "candle");
+    assert_eq!(msgpack["request_id"], "r-1");
+    assert_eq!(msgpack["candle"]["close"], 243.85);
+    assert_eq!(msgpack["candle"]["volume"], 55_740_700);
+
+    let AxumMessage::Binary(bytes) = WireFormat::Cbor.encode(&candle_frame()).unwrap() else {
+        panic!("CBOR frames are binary");
+    };
+    let cbor: Value = ciborium::from_reader(bytes.as_ref()).unwrap();
+    assert_eq!(cbor, msgpack);
+    assert!(bytes.len() < text.len());
+}
+
+#[tokio::test]
+async fn negotiated_subprotocol_is_used_for_the_session() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let mut request = format!("ws://{addr}/ws?ticker=AAPL&start=2025-01-01&end=2025-01-01")
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_static("trademox.msgpack, trademox.json"),
+    );
+
+    let (mut ws, response) = connect_async(request).await.expect("Failed to connect");
+    assert_eq!(
+        response.headers()["sec-websocket-protocol"],
+        "trademox.msgpack"
+    );
+
+    let msg = timeout(Duration::from_secs(5), ws.next())
+        .await
+        .expect("Timed out waiting for frame")
+        .expect("Connection closed")
+        .expect("WebSocket error");
+    let Message::Binary(bytes) = msg else {
+        panic!("expected a binary frame, got {msg:?}");
+    };
+    let frame: Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(frame["type"], "snapshot_begin");
+    assert_eq!(frame["candles"], 1);
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn data_frames_are_encoded_by_their_stream() {
+    let app = build_app_with_provider(Arc::new(DayProvider::default()));
+    let req = TickerRequest {
+        ticker: "AAPL".into(),
+        start: "2025-01-01".into(),
+        end: "2025-01-02".into(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: false,
+        indicators: vec![],
+    };
+
+    let (out, mut rx) = channel(64);
+    spawn_stream(&out, &app, req, FrameTag::default(), WireFormat::Cbor, None);
+    let mut candles = Vec::new();
+    loop {
+        match timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            Outbound::Encoded(frame, AxumMessage::Binary(bytes)) => {
+                let decoded: Value = ciborium::from_reader(bytes.as_ref()).unwrap();
+                assert_eq!(decoded["seq"], frame.seq.unwrap());
+                candles.push(decoded["candle"]["close"].clone());
+            }
+            Outbound::Frame(frame) if matches!(frame.body, FrameBody::SnapshotEnd { .. }) => break,
+            Outbound::Frame(_) => {}
+            other => panic!("unexpected outbound {other:?}"),
+        }
+    }
+    assert_eq!(candles, [1.0, 2.0]);
+}
@@ -0,0 +1,367 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::time::{sleep, timeout};
+use tradebox::app::app_box::{build_app_with_provider, AppBox};
+use tradebox::configs::yaml_config::LiveConfig;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request::TickerRequest;
+use tradebox::websocket::ws_codec::WireFormat;
+use tradebox::websocket::ws_hub::FeedHub;
+use tradebox::websocket::ws_protocol::{FrameBody, FrameTag};
+use tradebox::websocket::ws_sender::{spawn_stream, Outbound};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn request(start: NaiveDate, end: NaiveDate, live: bool) -> TickerRequest {
+    TickerRequest {
+        ticker: "AAPL".into(),
+        start: start.to_string(),
+        end: end.to_string(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live,
+        indicators: vec![],
+    }
+}
+
+fn candle(day: NaiveDate, close: i64) -> MarketCandle {
+    let price = Decimal::from(close);
+    MarketCandle {
+        ts: day.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: Some(100),
+        unadjusted_close: None,
+    }
+}
+
+async fn next_frame(rx: &mut Receiver<Outbound>) -> Value {
+    loop {
+        let outbound = timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("frame expected")
+            .expect("stream open");
+        if let Some(frame) = outbound.into_frame() {
+            return serde_json::to_value(&frame).unwrap();
+        }
+    }
+}
+
+async fn read_until_end(rx: &mut Receiver<Outbound>) -> usize {
+    let mut candles = 0;
+    loop {
+        let frame = next_frame(rx).await;
+        match frame["type"].as_str().unwrap() {
+            "candle" => candles += 1,
+            "snapshot_end" => return candles,
+            _ => {}
+        }
+    }
+}
+
+// Every live poll starts one more daily bar, closing the one before
+#[derive(Debug, Default)]
+struct RollingProvider {
+    fetches: AtomicUsize,
+    polls: AtomicUsize,
+}
+
+#[async_trait]
+impl MarketDataProvider for RollingProvider {
+    fn name(&self) -> &str {
+        "rolling"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        Ok(MarketHistory {
+            candles: start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .map(|d| candle(d, 10))
+                .collect(),
+            actions: vec![],
+            stale: false,
+            source: None,
+            gaps: vec![],
+        })
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        let polls = self.polls.fetch_add(1, Ordering::SeqCst) as i64 + 1;
+        let last = Utc::now().date_naive() + Duration::days(polls);
+        Ok(since
+            .iter_days()
+            .take_while(|d| *d <= last)
+            .map(|d| candle(d, 10 + polls))
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn identical_requests_share_one_fetch() {
+    let provider = Arc::new(RollingProvider::default());
+    let app = build_app_with_provider(provider.clone());
+    let req = request(date(2025, 1, 1), date(2025, 1, 5), false);
+
+    // held open so both streams find the feed alive
+    let feed = app.hub.join(&req);
+    let (out, mut rx) = channel(256);
+    let paced = TickerRequest {
+        pace: "rate:1000".into(),
+        ..req.clone()
+    };
+    let first = spawn_stream(
+        &out,
+        &app,
+        req.clone(),
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+    let second = spawn_stream(
+        &out,
+        &app,
+        paced,
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+    assert_eq!(read_until_end(&mut rx).await, 5);
+    assert_eq!(read_until_end(&mut rx).await, 5);
+    assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+    assert_eq!(app.hub.feed_count(), 1);
+
+    // another interval is a different feed
+    let weekly = TickerRequest {
+        interval: "1wk".into(),
+        ..req.clone()
+    };
+    assert!(!Arc::ptr_eq(&feed, &app.hub.join(&weekly)));
+
+    first.abort();
+    second.abort();
+    drop(feed);
+    sleep(std::time::Duration::from_millis(20)).await;
+    assert_eq!(app.hub.feed_count(), 0);
+}
+
+#[tokio::test]
+async fn history_snapshots_are_shared_no_longer_than_the_ttl() {
+    let provider = Arc::new(RollingProvider::default());
+    let app = AppBox {
+        hub: Arc::new(FeedHub::new(16, std::time::Duration::from_millis(50))),
+        ..build_app_with_provider(provider.clone())
+    };
+    let req = request(date(2025, 1, 1), date(2025, 1, 5), false);
+
+    // a slow replay keeps the first feed alive
+    let feed = app.hub.join(&req);
+    feed.subscribe(&app).await.unwrap();
+    assert!(Arc::ptr_eq(&feed, &app.hub.join(&req)));
+    assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+
+    sleep(std::time::Duration::from_millis(60)).await;
+    let fresh = app.hub.join(&req);
+    assert!(!Arc::ptr_eq(&feed, &fresh));
+    fresh.subscribe(&app).await.unwrap();
+    assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+    // joiners in the meantime share the fresh snapshot
+    assert!(Arc::ptr_eq(&fresh, &app.hub.join(&req)));
+}
+
+#[tokio::test]
+async fn live_feed_polls_once_for_all_consumers_and_stops_with_the_last() {
+    let provider = Arc::new(RollingProvider::default());
+    let app = AppBox {
+        live: LiveConfig {
+            poll_interval_ms: 20,
+        },
+        ..build_app_with_provider(provider.clone())
+    };
+    let today = Utc::now().date_naive();
+    let req = request(today - Duration::days(1), today, true);
+
+    let (out_a, mut rx_a) = channel(256);
+    let (out_b, mut rx_b) = channel(256);
+    let a = spawn_stream(
+        &out_a,
+        &app,
+        req.clone(),
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+    read_until_end(&mut rx_a).await;
+    let b = spawn_stream(
+        &out_b,
+        &app,
+        req,
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+    read_until_end(&mut rx_b).await;
+
+    let update_a = next_frame(&mut rx_a).await;
+    let update_b = next_frame(&mut rx_b).await;
+    assert!(update_a["type"].as_str().unwrap().starts_with("bar_"));
+    assert!(update_b["type"].as_str().unwrap().starts_with("bar_"));
+    assert_eq!(app.hub.feed_count(), 1);
+
+    a.abort();
+    b.abort();
+    sleep(std::time::Duration::from_millis(50)).await;
+    assert_eq!(app.hub.feed_count(), 0);
+    let polls = provider.polls.load(Ordering::SeqCst);
+    sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(provider.polls.load(Ordering::SeqCst), polls);
+}
+
+#[tokio::test]
+async fn lagging_consumer_is_resynced_from_feed_state() {
+    let app = AppBox {
+        live: LiveConfig {
+            poll_interval_ms: 5,
+        },
+        hub: Arc::new(FeedHub::new(1, std::time::Duration::from_secs(300))),
+        ..build_app_with_provider(Arc::new(RollingProvider::default()))
+    };
+    let today = Utc::now().date_naive();
+    let req = request(today - Duration::days(1), today, true);
+
+    // the stream blocks on a full channel while the feed keeps broadcasting
+    let (out, mut rx) = channel(1);
+    let stream = spawn_stream(&out, &app, req, FrameTag::default(), WireFormat::Json, None);
+    sleep(std::time::Duration::from_millis(200)).await;
+
+    // catch-up frames run until the forming bar that follows the `lagged` notice
+    let mut frames = Vec::new();
+    let mut lagged = None;
+    loop {
+        let frame = next_frame(&mut rx).await;
+        let is_update = frame["type"] == "bar_update";
+        if frame["type"] == "lagged" && lagged.is_none() {
+            lagged = Some(frames.len());
+        }
+        frames.push(frame);
+        if lagged.is_some() && is_update {
+            break;
+        }
+    }
+    stream.abort();
+
+    let lagged = lagged.unwrap();
+    assert!(frames[lagged]["skipped"].as_u64().unwrap() > 0);
+    assert!(frames[lagged + 1..]
+        .iter()
+        .all(|f| f["type"].as_str().unwrap().starts_with("bar_") || f["type"] == "lagged"));
+
+    let seqs = frames
+        .iter()
+        .filter_map(|f| f["seq"].as_u64())
+        .collect::<Vec<_>>();
+    assert!(seqs.windows(2).all(|w| w[1] == w[0] + 1));
+}
+
+#[tokio::test]
+async fn resume_picks_up_after_the_last_closed_bar_of_a_growing_feed() {
+    let app = AppBox {
+        live: LiveConfig {
+            poll_interval_ms: 20,
+        },
+        ..build_app_with_provider(Arc::new(RollingProvider::default()))
+    };
+    let today = Utc::now().date_naive();
+    let req = request(today - Duration::days(3), today, true);
+
+    // the first client sees the snapshot and one closed live bar, then drops
+    let (out, mut rx) = channel(256);
+    let first = spawn_stream(
+        &out,
+        &app,
+        req.clone(),
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+    let mut cursor = None;
+    while cursor.is_none() {
+        let Some(frame) = timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .into_frame()
+        else {
+            continue;
+        };
+        if let FrameBody::BarClosed { candle } = &frame.body {
+            assert_eq!(frame.cursor, Some(candle.time));
+            cursor = frame.cursor;
+        }
+    }
+    // the feed stays up and keeps growing while the client is away
+    let feed = app.hub.join(&req);
+    first.abort();
+    sleep(std::time::Duration::from_millis(60)).await;
+
+    let (out, mut rx) = channel(256);
+    let resumed = spawn_stream(
+        &out,
+        &app,
+        req,
+        FrameTag::default(),
+        WireFormat::Json,
+        cursor,
+    );
+    let mut times = Vec::new();
+    loop {
+        let frame = next_frame(&mut rx).await;
+        match frame["type"].as_str().unwrap() {
+            "candle" => times.push(frame["candle"]["time"].as_str().unwrap().to_string()),
+            "snapshot_end" => break,
+            _ => {}
+        }
+    }
+    let next = (cursor.unwrap() + Duration::days(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    assert_eq!(times.first(), Some(&next));
+
+    resumed.abort();
+    drop(feed);
+}
@@ -0,0 +1,256 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::channel;
+use tokio::time::timeout;
+use tradebox::app::app_box::{build_app_with_provider, AppBox};
+use tradebox::configs::yaml_config::LiveConfig;
+use tradebox::creator::tk_creator::TickerParams;
+use tradebox::data::md_provider::{
+    MarketCandle, MarketDataProvider, MarketHistory, ProviderCapabilities,
+};
+use tradebox::indicators::ind_spec::IndicatorSpec;
+use tradebox::requests::tk_interval::TickerInterval;
+use tradebox::requests::tk_request::TickerRequest;
+use tradebox::websocket::ws_codec::WireFormat;
+use tradebox::websocket::ws_live::LiveTracker;
+use tradebox::websocket::ws_protocol::{FrameBody, FrameTag};
+use tradebox::websocket::ws_sender::{spawn_stream, Outbound};
+
+fn midnight(day: NaiveDate) -> DateTime<Utc> {
+    day.and_time(NaiveTime::MIN).and_utc()
+}
+
+fn params(day: NaiveDate, close: i64) -> TickerParams {
+    TickerParams {
+        time: midnight(day),
+        open: Decimal::from(close),
+        high: Decimal::from(close),
+        low: Decimal::from(close),
+        close: Decimal::from(close),
+        volume: Some(100),
+        unadjusted_close: None,
+        indicators: BTreeMap::new(),
+    }
+}
+
+fn market(day: NaiveDate, close: i64) -> MarketCandle {
+    MarketCandle {
+        ts: midnight(day),
+        open: Decimal::from(close),
+        high: Decimal::from(close),
+        low: Decimal::from(close),
+        close: Decimal::from(close),
+        volume: Some(100),
+        unadjusted_close: None,
+    }
+}
+
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+// Daily history closing at 10; each live poll moves the forming bar, the third one closes it
+#[derive(Debug, Default)]
+struct TickingProvider {
+    polls: AtomicUsize,
+}
+
+#[async_trait]
+impl MarketDataProvider for TickingProvider {
+    fn name(&self) -> &str {
+        "ticking"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: vec![TickerInterval::OneDay],
+            supports_actions: false,
+            timezone: Tz::UTC,
+        }
+    }
+
+    async fn fetch_history_data(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<MarketHistory> {
+        Ok(MarketHistory {
+            candles: start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .map(|d| market(d, 10))
+                .collect(),
+            actions: vec![],
+            stale: false,
+            source: None,
+            gaps: vec![],
+        })
+    }
+
+    async fn fetch_latest_bars(
+        &self,
+        _ticker: &str,
+        _interval: TickerInterval,
+        since: NaiveDate,
+    ) -> Result<Vec<MarketCandle>> {
+        let today = today();
+        let polls = self.polls.fetch_add(1, Ordering::SeqCst) + 1;
+        let bars = match polls {
+            1 | 2 => vec![market(today, 11)],
+            _ => vec![market(today, 12), market(today + Duration::days(1), 13)],
+        };
+        Ok(bars
+            .into_iter()
+            .filter(|c| c.ts >= midnight(since))
+            .collect())
+    }
+}
+
+#[test]
+fn tracker_sends_changed_and_closed_bars_only() {
+    let day = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+    let mut tracker = LiveTracker::new(Some(params(day, 10)));
+    assert_eq!(tracker.since(), Some(day));
+
+    assert!(tracker.update(vec![params(day, 10)]).is_empty());
+
+    let frames = tracker.update(vec![params(day - Duration::days(1), 9), params(day, 11)]);
+    assert!(
+        matches!(&frames[..], [FrameBody::BarUpdate { candle }] if candle.close == Decimal::from(11))
+    );
+
+    let next = day + Duration::days(1);
+    let frames = tracker.update(vec![params(day, 12), params(next, 13)]);
+    assert!(matches!(
+        &frames[..],
+        [FrameBody::BarClosed { candle: closed }, FrameBody::BarUpdate { candle: forming }]
+            if closed.close == Decimal::from(12) && forming.time == midnight(next)
+    ));
+    assert_eq!(tracker.since(), Some(next));
+}
+
+#[tokio::test]
+async fn live_stream_polls_after_the_snapshot() {
+    let app = AppBox {
+        live: LiveConfig {
+            poll_interval_ms: 20,
+        },
+        ..build_app_with_provider(Arc::new(TickingProvider::default()))
+    };
+    let request = TickerRequest {
+        ticker: "AAPL".into(),
+        start: (today() - Duration::days(2)).to_string(),
+        end: today().to_string(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: true,
+        indicators: vec![],
+    };
+
+    let (out, mut rx) = channel(64);
+    let handle = spawn_stream(
+        &out,
+        &app,
+        request,
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+
+    let mut frames = Vec::new();
+    while frames.len() < 8 {
+        let Some(frame) = timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .and_then(Outbound::into_frame)
+        else {
+            continue;
+        };
+        frames.push(serde_json::to_value(&frame).unwrap());
+    }
+    handle.abort();
+
+    let types = frames
+        .iter()
+        .map(|f| f["type"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        [
+            "snapshot_begin",
+            "candle",
+            "candle",
+            "candle",
+            "snapshot_end",
+            "bar_update",
+            "bar_closed",
+            "bar_update"
+        ]
+    );
+    assert_eq!(frames[5]["candle"]["close"], Value::from("11"));
+    assert_eq!(frames[6]["candle"]["close"], Value::from("12"));
+    assert_eq!(frames[7]["candle"]["close"], Value::from("13"));
+    assert_eq!(frames[7]["seq"], 6);
+}
+
+#[tokio::test]
+async fn live_bars_carry_indicators_warmed_up_on_the_snapshot() {
+    let app = AppBox {
+        live: LiveConfig {
+            poll_interval_ms: 20,
+        },
+        ..build_app_with_provider(Arc::new(TickingProvider::default()))
+    };
+    let request = TickerRequest {
+        ticker: "AAPL".into(),
+        start: (today() - Duration::days(2)).to_string(),
+        end: today().to_string(),
+        interval: "1d".into(),
+        pace: "burst".into(),
+        batch: String::new(),
+        delivery: String::new(),
+        resample: String::new(),
+        live: true,
+        indicators: vec![IndicatorSpec::Sma { period: 2 }],
+    };
+
+    let (out, mut rx) = channel(64);
+    let handle = spawn_stream(
+        &out,
+        &app,
+        request,
+        FrameTag::default(),
+        WireFormat::Json,
+        None,
+    );
+
+    let mut live = Vec::new();
+    while live.len() < 3 {
+        let Some(frame) = timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .and_then(Outbound::into_frame)
+        else {
+            continue;
+        };
+        if let FrameBody::BarUpdate { candle } | FrameBody::BarClosed { candle } = frame.body {
+            live.push(candle.indicators["sma_2"]);
+        }
+    }
+    handle.abort();
+
+    // the first live bar averages with the last closed snapshot bar at 10
+    assert_eq!(live, [10.5, 11.0, 12.5]);
+}
@@ -0,0 +1,89 @@
+use chrono::{TimeZone, Utc};
+use std::time::Duration;
+use tradebox::configs::yaml_config::PacingConfig;
+use tradebox::requests::tk_pace::TickerPace;
+use tradebox::websocket::ws_pacer::Pacer;
+
+#[test]
+fn pace_parses_and_round_trips() {
+    for pace in ["burst", "burst:500", "rate:20", "replay:60"] {
+        assert_eq!(pace.parse::<TickerPace>().unwrap().to_string(), pace);
+    }
+    for pace in [
+        "", "fast", "rate", "rate:0", "rate:-1", "burst:0", "replay:x",
+    ] {
+        assert!(
+            pace.parse::<TickerPace>().is_err(),
+            "{pace} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn pacer_applies_server_limits() {
+    let config = PacingConfig {
+        max_rate: 50.0,
+        allow_burst: false,
+        ..PacingConfig::default()
+    };
+
+    assert_eq!(
+        Pacer::new("rate:200", &config).unwrap().pace(),
+        TickerPace::Rate { per_sec: 50.0 }
+    );
+    assert_eq!(
+        Pacer::new("burst", &config).unwrap().pace(),
+        TickerPace::Rate { per_sec: 50.0 }
+    );
+    assert_eq!(
+        Pacer::new("", &PacingConfig::default()).unwrap().pace(),
+        TickerPace::Rate { per_sec: 10.0 }
+    );
+
+    // no cap to fall back to: burst becomes the default rate, not rate:0
+    for max_rate in [0.0, -5.0] {
+        let config = PacingConfig {
+            max_rate,
+            allow_burst: false,
+            ..PacingConfig::default()
+        };
+        let mut pacer = Pacer::new("burst", &config).unwrap();
+        assert_eq!(pacer.pace(), TickerPace::default());
+        let ts = Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap();
+        pacer.delay(ts);
+        assert_eq!(pacer.delay(ts), Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn replay_scales_bar_gaps() {
+    let config = PacingConfig {
+        max_delay_ms: 2_000,
+        ..PacingConfig::default()
+    };
+    let mut pacer = Pacer::new("replay:3600", &config).unwrap();
+    let ts = |h| Utc.with_ymd_and_hms(2025, 1, 2, h, 0, 0).unwrap();
+
+    assert_eq!(pacer.delay(ts(9)), Duration::ZERO);
+    // one hour at 3600x is one second
+    assert_eq!(pacer.delay(ts(10)), Duration::from_secs(1));
+    // the overnight gap is capped by max_delay_ms
+    assert_eq!(pacer.delay(ts(23)), Duration::from_secs(2));
+    // duplicate timestamps still respect max_rate
+    assert_eq!(pacer.delay(ts(23)), Duration::from_millis(1));
+}
+
+#[test]
+fn tiny_rates_and_speeds_are_capped_instead_of_overflowing() {
+    let config = PacingConfig {
+        max_delay_ms: 2_000,
+        ..PacingConfig::default()
+    };
+    let ts = |h| Utc.with_ymd_and_hms(2025, 1, 2, h, 0, 0).unwrap();
+
+    for pace in ["rate:1e-300", "replay:1e-300", "rate:0.1"] {
+        let mut pacer = Pacer::new(pace, &config).unwrap();
+        assert_eq!(pacer.delay(ts(9)), Duration::ZERO);
+        assert_eq!(pacer.delay(ts(10)), Duration::from_secs(2), "{pace}");
+    }
+}
@@ -0,0 +1,259 @@
+mod common;
+
+use common::{start_test_server, DayProvider};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn next_json(ws: &mut WsStream) -> Value {
+    loop {
+        let msg = timeout(Duration::from_secs(5), ws.next())
+            .await
+            .expect("Timed out waiting for frame")
+            .expect("Connection closed")
+            .expect("WebSocket error");
+        if let Message::Text(text) = msg {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+async fn send_json(ws: &mut WsStream, value: Value) {
+    ws.send(Message::Text(value.to_string().into()))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn subscribe_list_and_unsubscribe_multiple_symbols() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let url = format!("ws://{addr}/ws?ticker=AAPL&start=2025-01-01&end=2025-01-01");
+    let (mut ws, _) = connect_async(&url).await.expect("Failed to connect");
+
+    // initial query stream is not bound to a subscription
+    let begin = next_json(&mut ws).await;
+    assert_eq!(begin["type"], "snapshot_begin");
+    assert!(begin.get("subscription_id").is_none());
+    assert_eq!(next_json(&mut ws).await["type"], "candle");
+    assert_eq!(next_json(&mut ws).await["type"], "snapshot_end");
+
+    send_json(
+        &mut ws,
+        json!({"v": 1, "op": "subscribe", "request_id": "r-1", "symbols": ["AAPL", "MSFT"], "start": "2025-01-01", "end": "2025-01-02"}),
+    )
+    .await;
+
+    let ack = next_json(&mut ws).await;
+    assert_eq!(ack["type"], "subscribed");
+    assert_eq!(ack["request_id"], "r-1");
+    assert_eq!(ack["symbols"], json!(["AAPL", "MSFT"]));
+    let subscription_id = ack["subscription_id"].as_str().unwrap().to_string();
+
+    let mut frames = Vec::new();
+    for _ in 0..8 {
+        let frame = next_json(&mut ws).await;
+        assert_eq!(frame["subscription_id"], subscription_id);
+        assert_eq!(frame["request_id"], "r-1");
+        frames.push(format!(
+            "{}:{}:{}",
+            frame["symbol"].as_str().unwrap(),
+            frame["type"].as_str().unwrap(),
+            frame["seq"]
+        ));
+    }
+    // symbols stream concurrently, so only the order within a symbol is fixed
+    for symbol in ["AAPL", "MSFT"] {
+        let symbol_frames = frames
+            .iter()
+            .filter(|f| f.starts_with(symbol))
+            .map(|f| f.split_once(':').unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbol_frames,
+            vec![
+                "snapshot_begin:null",
+                "candle:1",
+                "candle:2",
+                "snapshot_end:null"
+            ]
+        );
+    }
+
+    send_json(&mut ws, json!({"op": "list", "request_id": "r-2"})).await;
+    let list = next_json(&mut ws).await;
+    assert_eq!(list["type"], "subscriptions");
+    assert_eq!(list["request_id"], "r-2");
+    assert_eq!(list["subscriptions"][0]["subscription_id"], subscription_id);
+
+    send_json(
+        &mut ws,
+        json!({"op": "unsubscribe", "subscription_id": subscription_id}),
+    )
+    .await;
+    let removed = next_json(&mut ws).await;
+    assert_eq!(removed["type"], "unsubscribed");
+    assert_eq!(removed["symbols"], json!(["AAPL", "MSFT"]));
+
+    send_json(&mut ws, json!({"op": "ping", "request_id": "r-3"})).await;
+    let pong = next_json(&mut ws).await;
+    assert_eq!(pong["type"], "pong");
+    assert_eq!(pong["request_id"], "r-3");
+
+    send_json(&mut ws, json!({"v": 2, "op": "list", "request_id": "r-4"})).await;
+    let error = next_json(&mut ws).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["request_id"], "r-4");
+    assert!(error["error"]
+        .as_str()
+        .unwrap()
+        .contains("Unsupported protocol version"));
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn cancel_and_replace_stop_running_streams() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let url = format!("ws://{addr}/ws?ticker=AAPL&start=2025-01-01&end=2025-01-01");
+    let (mut ws, _) = connect_async(&url).await.expect("Failed to connect");
+    for _ in 0..3 {
+        next_json(&mut ws).await;
+    }
+
+    // a year of daily candles takes far longer to stream than the test waits
+    send_json(
+        &mut ws,
+        json!({"op": "subscribe", "request_id": "r-1", "symbols": ["AAPL"], "start": "2024-01-01", "end": "2024-12-31"}),
+    )
+    .await;
+    let ack = next_json(&mut ws).await;
+    let subscription_id = ack["subscription_id"].as_str().unwrap().to_string();
+    assert_eq!(next_json(&mut ws).await["type"], "snapshot_begin");
+
+    send_json(
+        &mut ws,
+        json!({"op": "subscribe", "request_id": "r-2", "symbols": ["MSFT"], "start": "2025-01-01", "end": "2025-01-01", "replace": subscription_id}),
+    )
+    .await;
+    let mut replaced = false;
+    loop {
+        let frame = next_json(&mut ws).await;
+        if frame["request_id"] == "r-2" && frame["type"] == "snapshot_end" {
+            break;
+        }
+        if frame["type"] == "subscribed" {
+            replaced = true;
+        }
+    }
+    assert!(replaced);
+
+    send_json(&mut ws, json!({"op": "list", "request_id": "r-3"})).await;
+    let list = next_json(&mut ws).await;
+    assert_eq!(list["subscriptions"].as_array().unwrap().len(), 1);
+    assert_eq!(list["subscriptions"][0]["symbols"], json!(["MSFT"]));
+
+    // the replaced stream sends nothing more
+    send_json(
+        &mut ws,
+        json!({"op": "subscribe", "request_id": "r-4", "symbols": ["IBM"], "start": "2024-01-01", "end": "2024-12-31"}),
+    )
+    .await;
+    assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+
+    send_json(
+        &mut ws,
+        json!({"op": "cancel", "request_id": "r-5", "target_request_id": "r-4"}),
+    )
+    .await;
+    let cancelled = loop {
+        let frame = next_json(&mut ws).await;
+        assert_ne!(frame["request_id"], "r-1");
+        if frame["type"] == "cancelled" {
+            break frame;
+        }
+    };
+    assert_eq!(cancelled["request_id"], "r-5");
+    assert_eq!(cancelled["streams"], 1);
+
+    send_json(&mut ws, json!({"op": "ping", "request_id": "r-6"})).await;
+    assert_eq!(next_json(&mut ws).await["type"], "pong");
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn subscribe_with_batch_sends_candle_arrays() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let url = format!("ws://{addr}/ws?ticker=AAPL&start=2025-01-01&end=2025-01-01");
+    let (mut ws, _) = connect_async(&url).await.expect("Failed to connect");
+    for _ in 0..3 {
+        next_json(&mut ws).await;
+    }
+
+    send_json(
+        &mut ws,
+        json!({"op": "subscribe", "symbols": ["AAPL"], "start": "2025-01-01", "end": "2025-01-05", "pace": "burst", "batch": "count:2"}),
+    )
+    .await;
+    assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+    assert_eq!(next_json(&mut ws).await["candles"], 5);
+
+    let mut sizes = Vec::new();
+    loop {
+        let frame = next_json(&mut ws).await;
+        if frame["type"] == "snapshot_end" {
+            break;
+        }
+        assert_eq!(frame["type"], "candles");
+        assert_eq!(frame["seq"], sizes.len() + 1);
+        sizes.push(frame["candles"].as_array().unwrap().len());
+    }
+    assert_eq!(sizes, vec![2, 2, 1]);
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn subscribe_with_arrow_delivery_sends_one_binary_batch() {
+    let (addr, shutdown_tx) = start_test_server(Arc::new(DayProvider::default())).await;
+    let url = format!("ws://{addr}/ws?ticker=AAPL&start=2025-01-01&end=2025-01-01");
+    let (mut ws, _) = connect_async(&url).await.expect("Failed to connect");
+    for _ in 0..3 {
+        next_json(&mut ws).await;
+    }
+
+    send_json(
+        &mut ws,
+        json!({"op": "subscribe", "symbols": ["AAPL"], "start": "2025-01-01", "end": "2025-01-31", "delivery": "arrow"}),
+    )
+    .await;
+    assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+    assert_eq!(next_json(&mut ws).await["type"], "snapshot_begin");
+
+    let header = next_json(&mut ws).await;
+    assert_eq!(header["type"], "arrow");
+    assert_eq!(header["rows"], 31);
+
+    let msg = timeout(Duration::from_secs(5), ws.next())
+        .await
+        .expect("Timed out waiting for frame")
+        .expect("Connection closed")
+        .expect("WebSocket error");
+    let Message::Binary(bytes) = msg else {
+        panic!("expected a binary frame, got {msg:?}");
+    };
+    assert_eq!(header["bytes"], bytes.len());
+    let mut reader = arrow_ipc::reader::StreamReader::try_new(bytes.as_ref(), None).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap().num_rows(), 31);
+
+    assert_eq!(next_json(&mut ws).await["type"], "snapshot_end");
+
+    let _ = shutdown_tx.send(());
+}
@@ -109,6 +109,30 @@ mod tests {
                 "ticker=BTC-USD&start=2023-01-01&end=not-a-date",
                 "InvalidDate",
             ),
+            (
+                "ticker=BTC-USD&start=2023-01-01&end=2023-01-10&interval=2h",
+                "InvalidInterval",
+            ),
+            (
+                "ticker=BTC-USD&start=2023-01-01&end=2023-01-10&pace=warp:9",
+                "InvalidPace",
+            ),
+            (
+                "ticker=BTC-USD&start=2023-01-01&end=2023-01-10&batch=count:0",
+                "InvalidBatch",
+            ),
+            (
+                "ticker=BTC-USD&start=2023-01-01&end=2023-01-10&delivery=parquet",
+                "InvalidDelivery",
+            ),
+            (
+                "ticker=BTC-USD&start=2023-01-01&end=2023-01-10&resample=5min",
+                "InvalidResample",
+            ),
+            (
+                "ticker=BTC-USD&start=2023-01-01&end=2023-01-10&live=true",
+                "InvalidDate",
+            ),
             ("invalid=param", "InvalidTicker"),
         ];
 